@@ -37,10 +37,14 @@ export class OracleOperations {
       this.logger.info(`Creating epoch ${nextEpochNumber} with ${usdcRewardAmount} USDC rewards`);
 
       const tx = await program.methods
-        .createEpoch(new anchor.BN(usdcRewardAmount))
+        .createEpoch(new anchor.BN(usdcRewardAmount), null)
         .accounts({
           config: configPda,
           epoch: epochPda,
+          previousEpoch: configData.currentEpoch > 0
+            ? this.client.findEpochPda(configData.currentEpoch)[0]
+            : null,
+          usdcVault: configData.usdcVault,
           oracle: this.config.oracleKeypair.publicKey,
           systemProgram: SystemProgram.programId,
         })
@@ -227,11 +231,12 @@ export class OracleOperations {
       this.logger.info(`Finalizing epoch ${epochNumber}`);
 
       const tx = await program.methods
-        .finalizeEpoch(new anchor.BN(epochNumber))
+        .finalizeEpoch(new anchor.BN(epochNumber), null)
         .accounts({
           config: configPda,
           epoch: epochPda,
           oracle: this.config.oracleKeypair.publicKey,
+          systemProgram: SystemProgram.programId,
         })
         .rpc();
 
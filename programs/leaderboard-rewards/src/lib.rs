@@ -1,21 +1,97 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    ed25519_program,
+    hash::hashv,
+    program_option::COption,
+    sysvar::instructions::{self as instructions_sysvar, load_current_index_checked, load_instruction_at_checked},
+};
+use anchor_lang::system_program;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{self, Mint, Token, TokenAccount, Transfer},
-    token_2022::{mint_to, MintTo, Token2022},
+    token_2022::{
+        initialize_mint2, mint_to,
+        spl_token_2022::{
+            self,
+            extension::{
+                transfer_fee::TransferFeeConfig, BaseStateWithExtensions, ExtensionType,
+                StateWithExtensions,
+            },
+        },
+        InitializeMint2, MintTo, Token2022,
+    },
+    token_2022_extensions::{
+        metadata_pointer_initialize, non_transferable_mint_initialize,
+        spl_token_metadata_interface::state::TokenMetadata, token_metadata_initialize,
+        MetadataPointerInitialize, NonTransferableMintInitialize, TokenMetadataInitialize,
+    },
+    token_interface::{
+        get_mint_extension_data, transfer_checked, Mint, TokenAccount, TokenInterface,
+        TransferChecked,
+    },
 };
 
 declare_id!("HHU31ZnG6NrdXYLseioh5hhDwBX1Zwmv2nyrfiC46yHc");
 
 const MAX_GITHUB_USERNAME_LEN: usize = 39;
 const SECONDS_PER_WEEK: i64 = 7 * 24 * 60 * 60;
+const MAX_EPOCH_DURATION_SECS: i64 = 365 * 24 * 60 * 60;
 const CLAIM_WINDOW_DAYS: i64 = 30 * 24 * 60 * 60;
+const RETROACTIVE_WINDOW_SECS: i64 = 90 * 24 * 60 * 60;
 const MAX_XP_INCREASE: u64 = 1_000_000;
 const XP_TO_SBT_RATIO: u64 = 100;
+const MIN_CLAIM_WINDOW_SECS: i64 = 24 * 60 * 60;
+const MAX_CLAIM_WINDOW_SECS: i64 = 365 * 24 * 60 * 60;
+const MAX_XP_TO_SBT_RATIO: u64 = 1_000_000;
+// Keeps the SBT earned by a single sync within u64 at any allowed ratio
+const MAX_XP_INCREASE_LIMIT: u64 = u64::MAX / MAX_XP_TO_SBT_RATIO;
+const MAX_SYNC_INTERVAL_SECS: i64 = 7 * 24 * 60 * 60;
+const PAYOUT_TIMELOCK_SECS: i64 = 2 * 24 * 60 * 60;
+
+// v1 accounts predate the version byte; migrations detect them by their shorter data length
+const CONFIG_VERSION: u8 = 2;
+const CONTRIBUTOR_VERSION: u8 = 2;
+const SNAPSHOT_VERSION: u8 = 2;
+const EPOCH_VERSION: u8 = 2;
+const CONFIG_RESERVED_LEN: usize = 6;
+const CONTRIBUTOR_RESERVED_LEN: usize = 8;
+const SNAPSHOT_RESERVED_LEN: usize = 32;
+const EPOCH_RESERVED_LEN: usize = 16;
 const SBT_MAX_SUPPLY: u64 = 1_000_000_000;
+const SBT_DECIMALS: u8 = 0;
+const MAX_SBT_NAME_LEN: usize = 32;
+const MAX_SBT_SYMBOL_LEN: usize = 10;
+const MAX_SBT_URI_LEN: usize = 200;
 const MAX_ROLE_CATEGORIES: usize = 10;
 const MAX_DOMAIN_CATEGORIES: usize = 15;
 const MAX_SKILL_CATEGORIES: usize = 20;
+const MAX_BATCH_SYNC_ENTRIES: usize = 10;
+const MAX_BATCH_CLAIM_EPOCHS: usize = 8;
+const MAX_ORACLES: usize = 3;
+const PAUSE_SYNC_XP: u8 = 1 << 0;
+const PAUSE_CREATE_EPOCH: u8 = 1 << 1;
+const PAUSE_CLAIM_USDC: u8 = 1 << 2;
+const PAUSE_CLAIM_SBT: u8 = 1 << 3;
+const PAUSE_FUND_POOL: u8 = 1 << 4;
+const PAUSE_ALL: u8 = PAUSE_SYNC_XP | PAUSE_CREATE_EPOCH | PAUSE_CLAIM_USDC | PAUSE_CLAIM_SBT | PAUSE_FUND_POOL;
+const MAX_MERKLE_LEAVES: u32 = 65_536;
+const MAX_MERKLE_PROOF_LEN: usize = 32;
+const MERKLE_LEAF_PREFIX: u8 = 0;
+const MERKLE_NODE_PREFIX: u8 = 1;
+const UNREGISTERED_LEAF_PREFIX: u8 = 2;
+const XP_ATTESTATION_DOMAIN: &[u8] = b"leaderboard-rewards:xp-attestation";
+const ED25519_OFFSETS_START: usize = 2;
+const ED25519_OFFSETS_LEN: usize = 14;
+
+// Migrations only ever grow accounts, and finalize_epoch grows an epoch by the whole claim
+// bitmap in one instruction
+const _: () = assert!(Config::SPACE > Config::V1_SPACE);
+const _: () = assert!(Contributor::SPACE > Contributor::V1_SPACE);
+const _: () = assert!(EpochSnapshot::SPACE > EpochSnapshot::V1_SPACE);
+const _: () = assert!(RewardEpoch::space(0) > RewardEpoch::V1_SPACE);
+const _: () = assert!(
+    RewardEpoch::space(MAX_MERKLE_LEAVES) - RewardEpoch::space(0)
+        <= anchor_lang::solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE
+);
 
 #[program]
 pub mod leaderboard_rewards {
@@ -27,6 +103,15 @@ pub mod leaderboard_rewards {
         usdc_mint: Pubkey,
         sbt_mint: Pubkey,
     ) -> Result<()> {
+        // A pre-existing SBT mint must already be soulbound; otherwise leave it unset for create_sbt_mint
+        match ctx.accounts.sbt_mint_account.as_ref() {
+            Some(mint) => {
+                require_keys_eq!(mint.key(), sbt_mint, ErrorCode::InvalidSbtMint);
+                validate_sbt_mint(&mint.to_account_info(), &ctx.accounts.config.key())?;
+            }
+            None => require!(sbt_mint == Pubkey::default(), ErrorCode::InvalidSbtMint),
+        }
+        
         let config = &mut ctx.accounts.config;
         config.admin = ctx.accounts.admin.key();
         config.oracle = oracle;
@@ -36,9 +121,21 @@ pub mod leaderboard_rewards {
         config.current_epoch = 0;
         config.total_epochs = 0;
         config.xp_to_sbt_ratio = XP_TO_SBT_RATIO;
+        config.max_xp_increase = MAX_XP_INCREASE;
+        config.claim_window_secs = CLAIM_WINDOW_DAYS;
+        config.retroactive_window_secs = RETROACTIVE_WINDOW_SECS;
         config.sbt_total_supply = SBT_MAX_SUPPLY;
         config.sbt_minted = 0;
+        config.xp_weighting = XpWeighting::Cumulative;
+        config.treasury = Pubkey::default();
+        config.usdc_committed = 0;
+        config.pending_admin = Pubkey::default();
+        config.paused_flags = 0;
+        config.epoch_duration_secs = SECONDS_PER_WEEK;
+        config.last_epoch_end = 0;
         config.bump = ctx.bumps.config;
+        config.version = CONFIG_VERSION;
+        config.reserved = [0u8; CONFIG_RESERVED_LEN];
 
         msg!("Program initialized with admin: {}", config.admin);
         msg!("Oracle: {}", config.oracle);
@@ -49,6 +146,93 @@ pub mod leaderboard_rewards {
         Ok(())
     }
 
+    pub fn create_sbt_mint(
+        ctx: Context<CreateSbtMint>,
+        name: String,
+        symbol: String,
+        uri: String,
+    ) -> Result<()> {
+        require!(name.len() <= MAX_SBT_NAME_LEN, ErrorCode::InvalidSbtMetadata);
+        require!(symbol.len() <= MAX_SBT_SYMBOL_LEN, ErrorCode::InvalidSbtMetadata);
+        require!(uri.len() <= MAX_SBT_URI_LEN, ErrorCode::InvalidSbtMetadata);
+        
+        let config = ctx.accounts.config.to_account_info();
+        let mint = ctx.accounts.sbt_mint.to_account_info();
+        let admin = ctx.accounts.admin.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
+        let token_program = ctx.accounts.token_2022_program.to_account_info();
+        let config_seeds: &[&[u8]] = &[b"config", &[ctx.accounts.config.bump]];
+        
+        let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[
+            ExtensionType::NonTransferable,
+            ExtensionType::MetadataPointer,
+        ])?;
+        create_pda_account(
+            &mint,
+            &admin,
+            &system_program,
+            space,
+            &token_program.key(),
+            &[b"sbt_mint", &[ctx.bumps.sbt_mint]],
+        )?;
+        
+        non_transferable_mint_initialize(CpiContext::new(
+            token_program.clone(),
+            NonTransferableMintInitialize {
+                token_program_id: token_program.clone(),
+                mint: mint.clone(),
+            },
+        ))?;
+        metadata_pointer_initialize(
+            CpiContext::new(
+                token_program.clone(),
+                MetadataPointerInitialize {
+                    token_program_id: token_program.clone(),
+                    mint: mint.clone(),
+                },
+            ),
+            Some(config.key()),
+            Some(mint.key()),
+        )?;
+        initialize_mint2(
+            CpiContext::new(token_program.clone(), InitializeMint2 { mint: mint.clone() }),
+            SBT_DECIMALS,
+            &config.key(),
+            Some(&config.key()),
+        )?;
+        
+        // Token-2022 grows the mint for the metadata entry itself but expects the rent up front
+        let metadata_len = TokenMetadata {
+            name: name.clone(),
+            symbol: symbol.clone(),
+            uri: uri.clone(),
+            ..Default::default()
+        }
+        .tlv_size_of()?;
+        fund_rent(&mint, &admin, &system_program, space + metadata_len)?;
+        token_metadata_initialize(
+            CpiContext::new_with_signer(
+                token_program.clone(),
+                TokenMetadataInitialize {
+                    program_id: token_program,
+                    metadata: mint.clone(),
+                    update_authority: config.clone(),
+                    mint_authority: config,
+                    mint: mint.clone(),
+                },
+                &[config_seeds],
+            ),
+            name,
+            symbol,
+            uri,
+        )?;
+        
+        ctx.accounts.config.sbt_mint = mint.key();
+        
+        msg!("Created soulbound SBT mint: {}", mint.key());
+        Ok(())
+    }
+
     pub fn update_oracle(ctx: Context<UpdateOracle>) -> Result<()> {
         let config = &mut ctx.accounts.config;
         config.oracle = ctx.accounts.new_oracle.key();
@@ -56,42 +240,252 @@ pub mod leaderboard_rewards {
         Ok(())
     }
 
+    pub fn propose_admin(ctx: Context<UpdateConfig>, new_admin: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.pending_admin = new_admin;
+        if new_admin == Pubkey::default() {
+            msg!("Pending admin transfer cancelled");
+        } else {
+            msg!("Proposed admin transfer to: {}", new_admin);
+        }
+        Ok(())
+    }
+
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let previous_admin = config.admin;
+        config.admin = ctx.accounts.pending_admin.key();
+        config.pending_admin = Pubkey::default();
+        msg!("Admin transferred from {} to {}", previous_admin, config.admin);
+        Ok(())
+    }
+
+    pub fn set_pause(ctx: Context<UpdateConfig>, paused_flags: u8) -> Result<()> {
+        require!(paused_flags & !PAUSE_ALL == 0, ErrorCode::InvalidPauseFlags);
+        
+        let config = &mut ctx.accounts.config;
+        config.paused_flags = paused_flags;
+        msg!("Pause flags set to {:#07b}", paused_flags);
+        Ok(())
+    }
+
+    pub fn set_epoch_duration(ctx: Context<UpdateConfig>, epoch_duration_secs: i64) -> Result<()> {
+        require!(
+            epoch_duration_secs > 0 && epoch_duration_secs <= MAX_EPOCH_DURATION_SECS,
+            ErrorCode::InvalidEpochDuration
+        );
+        
+        let config = &mut ctx.accounts.config;
+        config.epoch_duration_secs = epoch_duration_secs;
+        msg!("Epoch duration set to {} seconds (applies from the next epoch)", epoch_duration_secs);
+        Ok(())
+    }
+
+    pub fn update_params(ctx: Context<UpdateConfig>, params: ProtocolParams) -> Result<()> {
+        require!(
+            params.max_xp_increase > 0 && params.max_xp_increase <= MAX_XP_INCREASE_LIMIT,
+            ErrorCode::InvalidParams
+        );
+        require!(
+            params.claim_window_secs >= MIN_CLAIM_WINDOW_SECS && params.claim_window_secs <= MAX_CLAIM_WINDOW_SECS,
+            ErrorCode::InvalidParams
+        );
+        require!(
+            params.retroactive_window_secs >= params.claim_window_secs
+                && params.retroactive_window_secs <= MAX_CLAIM_WINDOW_SECS,
+            ErrorCode::InvalidParams
+        );
+        require!(
+            params.xp_to_sbt_ratio > 0 && params.xp_to_sbt_ratio <= MAX_XP_TO_SBT_RATIO,
+            ErrorCode::InvalidParams
+        );
+        require!(
+            params.min_sync_interval_secs >= 0 && params.min_sync_interval_secs <= MAX_SYNC_INTERVAL_SECS,
+            ErrorCode::InvalidParams
+        );
+        
+        let config = &mut ctx.accounts.config;
+        config.max_xp_increase = params.max_xp_increase;
+        config.claim_window_secs = params.claim_window_secs;
+        config.retroactive_window_secs = params.retroactive_window_secs;
+        config.xp_to_sbt_ratio = params.xp_to_sbt_ratio;
+        config.max_xp_per_epoch = params.max_xp_per_epoch;
+        config.max_sbt_per_epoch = params.max_sbt_per_epoch;
+        config.min_sync_interval_secs = params.min_sync_interval_secs;
+        
+        emit!(ParamsUpdatedEvent {
+            max_xp_increase: params.max_xp_increase,
+            claim_window_secs: params.claim_window_secs,
+            retroactive_window_secs: params.retroactive_window_secs,
+            xp_to_sbt_ratio: params.xp_to_sbt_ratio,
+            max_xp_per_epoch: params.max_xp_per_epoch,
+            max_sbt_per_epoch: params.max_sbt_per_epoch,
+            min_sync_interval_secs: params.min_sync_interval_secs,
+            admin: ctx.accounts.admin.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        msg!("Protocol parameters updated");
+        Ok(())
+    }
+
+    pub fn set_oracle_set(ctx: Context<UpdateConfig>, oracles: Vec<Pubkey>, threshold: u8) -> Result<()> {
+        require!(oracles.len() <= MAX_ORACLES, ErrorCode::InvalidOracleSet);
+        if oracles.is_empty() {
+            require!(threshold == 0, ErrorCode::InvalidOracleSet);
+        } else {
+            // A threshold of one would let a single key inflate XP again
+            require!(
+                threshold >= 2 && threshold as usize <= oracles.len(),
+                ErrorCode::InvalidOracleSet
+            );
+        }
+        for (i, oracle) in oracles.iter().enumerate() {
+            require!(*oracle != Pubkey::default(), ErrorCode::InvalidOracleSet);
+            require!(!oracles[..i].contains(oracle), ErrorCode::InvalidOracleSet);
+        }
+        
+        let config = &mut ctx.accounts.config;
+        config.oracles = [Pubkey::default(); MAX_ORACLES];
+        config.oracles[..oracles.len()].copy_from_slice(&oracles);
+        config.oracle_count = oracles.len() as u8;
+        config.oracle_threshold = threshold;
+        
+        msg!("Oracle set updated: {}-of-{}", threshold, oracles.len());
+        Ok(())
+    }
+
+    pub fn set_xp_weighting(ctx: Context<UpdateConfig>, xp_weighting: XpWeighting) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.xp_weighting = xp_weighting;
+        msg!("XP weighting set to {:?} (applies from the next epoch)", xp_weighting);
+        Ok(())
+    }
+
+    pub fn set_treasury(ctx: Context<UpdateConfig>, treasury: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.treasury = treasury;
+        msg!("Treasury set to: {}", treasury);
+        Ok(())
+    }
+
     pub fn fund_usdc_pool(ctx: Context<FundUsdcPool>, amount: u64) -> Result<()> {
-        token::transfer(
+        ctx.accounts.config.require_not_paused(PAUSE_FUND_POOL)?;
+        
+        transfer_checked(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
-                Transfer {
+                TransferChecked {
                     from: ctx.accounts.funder_token_account.to_account_info(),
+                    mint: ctx.accounts.usdc_mint.to_account_info(),
                     to: ctx.accounts.usdc_vault.to_account_info(),
                     authority: ctx.accounts.funder.to_account_info(),
                 },
             ),
             amount,
+            ctx.accounts.usdc_mint.decimals,
         )?;
-        msg!("Funded USDC pool with {} tokens", amount);
+        
+        let fee = transfer_fee(&ctx.accounts.usdc_mint, amount)?;
+        msg!("Funded USDC pool with {} tokens ({} withheld as transfer fee)", amount - fee, fee);
         Ok(())
     }
 
-    pub fn create_epoch(ctx: Context<CreateEpoch>, usdc_reward_amount: u64) -> Result<()> {
+    pub fn create_epoch(
+        ctx: Context<CreateEpoch>,
+        usdc_reward_amount: u64,
+        start_time: Option<i64>,
+    ) -> Result<()> {
         let config = &mut ctx.accounts.config;
         let epoch = &mut ctx.accounts.epoch;
         
+        config.require_not_paused(PAUSE_CREATE_EPOCH)?;
+        
         let clock = Clock::get()?;
         let epoch_number = config.current_epoch + 1;
         
+        let start_time = start_time.unwrap_or(clock.unix_timestamp);
+        let end_time = start_time
+            .checked_add(config.epoch_duration_secs)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        require!(start_time >= config.last_epoch_end, ErrorCode::EpochOverlap);
+        require!(
+            start_time <= clock.unix_timestamp + config.epoch_duration_secs && end_time > clock.unix_timestamp,
+            ErrorCode::InvalidEpochStart
+        );
+        
+        if config.current_epoch > 0 {
+            let previous_epoch = ctx
+                .accounts
+                .previous_epoch
+                .as_mut()
+                .ok_or(ErrorCode::MissingPreviousEpoch)?;
+            
+            if !previous_epoch.finalized {
+                require!(
+                    clock.unix_timestamp >= previous_epoch.end_time,
+                    ErrorCode::PreviousEpochNotFinalized
+                );
+                previous_epoch.finalized = true;
+                
+                msg!(
+                    "Epoch {} auto-finalized with {} contributors and {} total XP",
+                    previous_epoch.epoch_number,
+                    previous_epoch.contributor_count,
+                    previous_epoch.total_xp
+                );
+            }
+        }
+        
+        let usdc_committed = config
+            .usdc_committed
+            .checked_add(usdc_reward_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(
+            ctx.accounts.usdc_vault.amount >= usdc_committed,
+            ErrorCode::InsufficientVaultBalance
+        );
+        
         epoch.epoch_number = epoch_number;
-        epoch.start_time = clock.unix_timestamp;
-        epoch.end_time = clock.unix_timestamp + SECONDS_PER_WEEK;
+        epoch.start_time = start_time;
+        epoch.end_time = end_time;
         epoch.total_xp = 0;
         epoch.usdc_reward_amount = usdc_reward_amount;
         epoch.contributor_count = 0;
         epoch.finalized = false;
         epoch.bump = ctx.bumps.epoch;
+        epoch.version = EPOCH_VERSION;
+        epoch.merkle_root = [0u8; 32];
+        epoch.merkle_leaf_count = 0;
+        epoch.unregistered_xp_root = [0u8; 32];
+        epoch.unregistered_total_xp = 0;
+        epoch.xp_weighting = config.xp_weighting;
+        epoch.usdc_claimed_total = 0;
+        epoch.usdc_swept = 0;
+        epoch.swept = false;
+        epoch.snapshot_count = 0;
+        epoch.xp_issued = 0;
+        epoch.sbt_issued = 0;
+        epoch.claim_window_secs = config.claim_window_secs;
+        epoch.retroactive_window_secs = config.retroactive_window_secs;
+        epoch.reserved = [0u8; EPOCH_RESERVED_LEN];
+        epoch.claim_bitmap = Vec::new();
         
         config.current_epoch = epoch_number;
         config.total_epochs += 1;
+        config.usdc_committed = usdc_committed;
+        config.last_epoch_end = end_time;
         
-        msg!("Created epoch {} with {} USDC rewards", epoch_number, usdc_reward_amount);
+        msg!(
+            "Created epoch {} ({} to {}) with {} USDC rewards ({} committed, vault holds {})",
+            epoch_number,
+            start_time,
+            end_time,
+            usdc_reward_amount,
+            usdc_committed,
+            ctx.accounts.usdc_vault.amount
+        );
         Ok(())
     }
 
@@ -104,8 +498,9 @@ pub mod leaderboard_rewards {
         domain_xp_data: Vec<XpCategory>,
         skill_xp_data: Vec<XpCategory>,
     ) -> Result<()> {
+        ctx.accounts.config.require_not_paused(PAUSE_SYNC_XP)?;
         require!(
-            github_username.len() > 0 && github_username.len() <= MAX_GITHUB_USERNAME_LEN,
+            !github_username.is_empty() && github_username.len() <= MAX_GITHUB_USERNAME_LEN,
             ErrorCode::InvalidGithubUsername
         );
         require!(role_xp_data.len() <= MAX_ROLE_CATEGORIES, ErrorCode::TooManyCategories);
@@ -117,55 +512,27 @@ pub mod leaderboard_rewards {
         let clock = Clock::get()?;
         
         require!(!epoch.finalized, ErrorCode::EpochFinalized);
+        require!(clock.unix_timestamp >= epoch.start_time, ErrorCode::EpochNotStarted);
+        
+        if ctx.accounts.snapshot.funder == Pubkey::default() {
+            ctx.accounts.snapshot.funder = ctx.accounts.oracle.key();
+            epoch.snapshot_count += 1;
+        }
         
         let is_registered = ctx.accounts.contributor.wallet == wallet;
         let mut sbt_earned = 0u64;
         
         if is_registered {
-            let contributor = &mut ctx.accounts.contributor;
-            let snapshot = &mut ctx.accounts.snapshot;
-            
-            let old_xp = contributor.total_xp;
-            let xp_delta = total_xp.saturating_sub(old_xp);
-            
-            require!(
-                xp_delta <= MAX_XP_INCREASE,
-                ErrorCode::XpTooHigh
-            );
-            
-            let is_new_snapshot = snapshot.xp == 0;
-            let snapshot_old_xp = snapshot.xp;
-            
-            sbt_earned = xp_delta.checked_mul(config.xp_to_sbt_ratio).unwrap_or(0);
-            
-            contributor.total_xp = total_xp;
-            contributor.total_sbt_claimable = contributor
-                .total_sbt_claimable
-                .checked_add(sbt_earned)
-                .ok_or(ErrorCode::MathOverflow)?;
-            
-            snapshot.contributor = wallet;
-            snapshot.epoch = epoch.epoch_number;
-            snapshot.xp = total_xp;
-            snapshot.usdc_claimed = false;
-            snapshot.sbt_earned = sbt_earned;
-            snapshot.bump = ctx.bumps.snapshot;
-            
-            if is_new_snapshot {
-                epoch.contributor_count += 1;
-                epoch.total_xp = epoch.total_xp
-                    .checked_add(total_xp)
-                    .ok_or(ErrorCode::MathOverflow)?;
-            } else {
-                epoch.total_xp = epoch.total_xp
-                    .checked_sub(snapshot_old_xp)
-                    .ok_or(ErrorCode::MathOverflow)?
-                    .checked_add(total_xp)
-                    .ok_or(ErrorCode::MathOverflow)?;
-            }
-            
-            msg!("Synced XP {} for {} in epoch {} (+{} SBT)", 
-                total_xp, wallet, epoch.epoch_number, sbt_earned);
+            require!(config.oracle_threshold == 0, ErrorCode::ThresholdAttestationRequired);
+            sbt_earned = apply_xp_sync(
+                config,
+                epoch,
+                &mut ctx.accounts.contributor,
+                &mut ctx.accounts.snapshot,
+                wallet,
+                total_xp,
+                ctx.bumps.snapshot,
+            )?;
         } else {
             msg!("Emitting XP event for unregistered user: {}", wallet);
         }
@@ -186,485 +553,2698 @@ pub mod leaderboard_rewards {
         Ok(())
     }
 
-    pub fn finalize_epoch(ctx: Context<FinalizeEpoch>, epoch_number: u64) -> Result<()> {
-        let epoch = &mut ctx.accounts.epoch;
-        let clock = Clock::get()?;
-        
-        require!(!epoch.finalized, ErrorCode::EpochAlreadyFinalized);
-        require!(clock.unix_timestamp >= epoch.end_time, ErrorCode::EpochNotEnded);
-        
-        epoch.finalized = true;
-        
-        msg!(
-            "Epoch {} finalized with {} contributors and {} total XP",
-            epoch_number,
-            epoch.contributor_count,
-            epoch.total_xp
+    pub fn batch_sync_contributor_xp<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchSyncContributorXp<'info>>,
+        entries: Vec<XpSyncEntry>,
+    ) -> Result<()> {
+        ctx.accounts.config.require_not_paused(PAUSE_SYNC_XP)?;
+        require!(
+            !entries.is_empty() && entries.len() <= MAX_BATCH_SYNC_ENTRIES,
+            ErrorCode::InvalidBatchSize
         );
-        Ok(())
-    }
-
-    pub fn register_contributor(ctx: Context<RegisterContributor>, github_username: String) -> Result<()> {
         require!(
-            github_username.len() > 0 && github_username.len() <= MAX_GITHUB_USERNAME_LEN,
-            ErrorCode::InvalidGithubUsername
+            ctx.remaining_accounts.len() == entries.len() * 2,
+            ErrorCode::InvalidBatchAccount
         );
         
-        let contributor = &mut ctx.accounts.contributor;
+        let config = &ctx.accounts.config;
+        let epoch = &mut ctx.accounts.epoch;
+        let oracle = ctx.accounts.oracle.to_account_info();
+        let system_program = ctx.accounts.system_program.to_account_info();
         let clock = Clock::get()?;
         
-        contributor.wallet = ctx.accounts.wallet.key();
-        contributor.github_username = github_username.clone();
-        contributor.total_xp = 0;
-        contributor.total_sbt_claimable = 0;
-        contributor.total_sbt_claimed = 0;
-        contributor.lifetime_usdc_earned = 0;
-        contributor.last_claim_epoch = 0;
-        contributor.registered_at = clock.unix_timestamp;
-        contributor.bump = ctx.bumps.contributor;
+        require!(!epoch.finalized, ErrorCode::EpochFinalized);
+        require!(clock.unix_timestamp >= epoch.start_time, ErrorCode::EpochNotStarted);
+        
+        for (entry, accounts) in entries.into_iter().zip(ctx.remaining_accounts.chunks(2)) {
+            require!(
+                !entry.github_username.is_empty() && entry.github_username.len() <= MAX_GITHUB_USERNAME_LEN,
+                ErrorCode::InvalidGithubUsername
+            );
+            
+            let contributor_info = &accounts[0];
+            let snapshot_info = &accounts[1];
+            let mut sbt_earned = 0u64;
+            
+            let contributor = load_batch_contributor(contributor_info, &entry.wallet)?;
+            let is_registered = contributor.is_some();
+            
+            if let Some(mut contributor) = contributor {
+                require!(config.oracle_threshold == 0, ErrorCode::ThresholdAttestationRequired);
+                if snapshot_info.data_is_empty() {
+                    epoch.snapshot_count += 1;
+                }
+                
+                let (mut snapshot, snapshot_bump) = load_or_create_batch_snapshot(
+                    snapshot_info,
+                    &oracle,
+                    &system_program,
+                    epoch.epoch_number,
+                    &entry.wallet,
+                )?;
+                
+                sbt_earned = apply_xp_sync(
+                    config,
+                    epoch,
+                    &mut contributor,
+                    &mut snapshot,
+                    entry.wallet,
+                    entry.total_xp,
+                    snapshot_bump,
+                )?;
+                
+                contributor.try_serialize(&mut &mut contributor_info.try_borrow_mut_data()?[..])?;
+                snapshot.try_serialize(&mut &mut snapshot_info.try_borrow_mut_data()?[..])?;
+            } else {
+                msg!("Emitting XP event for unregistered user: {}", entry.wallet);
+            }
+            
+            emit!(XpSyncedEvent {
+                wallet: entry.wallet,
+                github_username: entry.github_username,
+                epoch: epoch.epoch_number,
+                total_xp: entry.total_xp,
+                role_xp: Vec::new(),
+                domain_xp: Vec::new(),
+                skill_xp: Vec::new(),
+                sbt_earned,
+                timestamp: clock.unix_timestamp,
+                is_registered,
+            });
+        }
         
-        msg!("Registered contributor {} with GitHub: {} at {}", 
-            contributor.wallet, github_username, contributor.registered_at);
         Ok(())
     }
 
-    pub fn update_github_link(ctx: Context<UpdateGithubLink>, new_github_username: String) -> Result<()> {
-        require!(
-            new_github_username.len() > 0 && new_github_username.len() <= MAX_GITHUB_USERNAME_LEN,
-            ErrorCode::InvalidGithubUsername
-        );
+    pub fn propose_xp_update(ctx: Context<ProposeXpUpdate>, wallet: Pubkey, total_xp: u64) -> Result<()> {
+        let config = &ctx.accounts.config;
+        config.require_not_paused(PAUSE_SYNC_XP)?;
+        require!(config.oracle_threshold > 0, ErrorCode::MultiOracleDisabled);
+        require!(config.is_oracle_set_member(&ctx.accounts.proposer.key()), ErrorCode::NotOracleSetMember);
+        require!(!ctx.accounts.epoch.finalized, ErrorCode::EpochFinalized);
         
-        let contributor = &mut ctx.accounts.contributor;
-        contributor.github_username = new_github_username.clone();
+        let proposal = &mut ctx.accounts.proposal;
+        proposal.epoch = ctx.accounts.epoch.epoch_number;
+        proposal.wallet = wallet;
+        proposal.total_xp = total_xp;
+        proposal.proposer = ctx.accounts.proposer.key();
+        proposal.approvers = [Pubkey::default(); MAX_ORACLES];
+        proposal.approvers[0] = ctx.accounts.proposer.key();
+        proposal.approval_count = 1;
+        proposal.created_at = Clock::get()?.unix_timestamp;
+        proposal.bump = ctx.bumps.proposal;
         
-        msg!("Updated GitHub username to: {}", new_github_username);
+        msg!(
+            "Proposed {} XP for {} in epoch {} (1 of {} approvals)",
+            total_xp,
+            wallet,
+            proposal.epoch,
+            config.oracle_threshold
+        );
         Ok(())
     }
 
-    pub fn claim_usdc_rewards(ctx: Context<ClaimUsdcRewards>, epoch_number: u64) -> Result<()> {
-        let epoch = &ctx.accounts.epoch;
-        let snapshot = &mut ctx.accounts.snapshot;
-        let contributor = &mut ctx.accounts.contributor;
-        let clock = Clock::get()?;
+    pub fn approve_xp_update(ctx: Context<ApproveXpUpdate>, wallet: Pubkey, total_xp: u64) -> Result<()> {
+        let config = &ctx.accounts.config;
+        let approver = ctx.accounts.approver.key();
+        config.require_not_paused(PAUSE_SYNC_XP)?;
+        require!(config.oracle_threshold > 0, ErrorCode::MultiOracleDisabled);
+        require!(config.is_oracle_set_member(&approver), ErrorCode::NotOracleSetMember);
         
-        require!(epoch.finalized, ErrorCode::EpochNotFinalized);
-        require!(!snapshot.usdc_claimed, ErrorCode::AlreadyClaimed);
-        require!(
-            clock.unix_timestamp <= epoch.end_time + CLAIM_WINDOW_DAYS,
-            ErrorCode::ClaimWindowExpired
-        );
-        require!(epoch.total_xp > 0, ErrorCode::NoXpInEpoch);
+        let epoch = &mut ctx.accounts.epoch;
+        let proposal = &mut ctx.accounts.proposal;
+        let clock = Clock::get()?;
         
-        let registration_time = contributor.registered_at;
-        let ninety_days_before_registration = registration_time - (90 * 24 * 60 * 60);
+        require!(!epoch.finalized, ErrorCode::EpochFinalized);
+        require!(clock.unix_timestamp >= epoch.start_time, ErrorCode::EpochNotStarted);
+        require!(!proposal.approvers.contains(&approver), ErrorCode::AlreadyApproved);
         
-        require!(
-            epoch.end_time >= ninety_days_before_registration,
-            ErrorCode::EpochTooOld
-        );
+        // Slots freed by oracles removed from the set are reused so the list never fills up
+        let slot = proposal
+            .approvers
+            .iter()
+            .position(|key| *key == Pubkey::default() || !config.is_oracle_set_member(key))
+            .ok_or(ErrorCode::AlreadyApproved)?;
+        proposal.approvers[slot] = approver;
+        proposal.approval_count = proposal
+            .approvers
+            .iter()
+            .filter(|key| config.is_oracle_set_member(key))
+            .count() as u8;
         
-        let reward_amount = (snapshot.xp as u128)
-            .checked_mul(epoch.usdc_reward_amount as u128)
-            .ok_or(ErrorCode::MathOverflow)?
-            .checked_div(epoch.total_xp as u128)
-            .ok_or(ErrorCode::MathOverflow)? as u64;
+        if proposal.approval_count < config.oracle_threshold {
+            msg!(
+                "Approved {} XP for {} ({} of {} approvals)",
+                total_xp,
+                wallet,
+                proposal.approval_count,
+                config.oracle_threshold
+            );
+            return Ok(());
+        }
         
-        require!(reward_amount > 0, ErrorCode::NoRewardToClaim);
+        let contributor = &mut ctx.accounts.contributor;
+        let snapshot_info = ctx.accounts.snapshot.to_account_info();
+        if snapshot_info.data_is_empty() {
+            epoch.snapshot_count += 1;
+        }
         
-        let seeds = &[b"config".as_ref(), &[ctx.accounts.config.bump]];
-        let signer = &[&seeds[..]];
+        let (mut snapshot, snapshot_bump) = load_or_create_batch_snapshot(
+            &snapshot_info,
+            &ctx.accounts.approver.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            epoch.epoch_number,
+            &wallet,
+        )?;
         
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.usdc_vault.to_account_info(),
-                    to: ctx.accounts.contributor_token_account.to_account_info(),
-                    authority: ctx.accounts.config.to_account_info(),
-                },
-                signer,
-            ),
-            reward_amount,
+        let sbt_earned = apply_xp_sync(
+            config,
+            epoch,
+            contributor,
+            &mut snapshot,
+            wallet,
+            total_xp,
+            snapshot_bump,
         )?;
+        snapshot.try_serialize(&mut &mut snapshot_info.try_borrow_mut_data()?[..])?;
         
-        snapshot.usdc_claimed = true;
-        contributor.lifetime_usdc_earned = contributor
-            .lifetime_usdc_earned
-            .checked_add(reward_amount)
-            .ok_or(ErrorCode::MathOverflow)?;
-        contributor.last_claim_epoch = epoch_number;
+        emit!(XpSyncedEvent {
+            wallet,
+            github_username: contributor.github_username.clone(),
+            epoch: epoch.epoch_number,
+            total_xp,
+            role_xp: Vec::new(),
+            domain_xp: Vec::new(),
+            skill_xp: Vec::new(),
+            sbt_earned,
+            timestamp: clock.unix_timestamp,
+            is_registered: true,
+        });
         
+        proposal.close(ctx.accounts.proposer.to_account_info())?;
+        Ok(())
+    }
+
+    pub fn cancel_xp_proposal(
+        _ctx: Context<CancelXpProposal>,
+        epoch_number: u64,
+        wallet: Pubkey,
+        total_xp: u64,
+    ) -> Result<()> {
         msg!(
-            "Claimed {} USDC for epoch {} (XP: {}/{}, within 90-day window)",
-            reward_amount,
-            epoch_number,
-            snapshot.xp,
-            epoch.total_xp
+            "Cancelled proposal of {} XP for {} in epoch {}",
+            total_xp,
+            wallet,
+            epoch_number
         );
         Ok(())
     }
 
-    pub fn claim_sbt_tokens(ctx: Context<ClaimSbtTokens>) -> Result<()> {
-        let claimable = ctx.accounts.contributor.total_sbt_claimable
-            .checked_sub(ctx.accounts.contributor.total_sbt_claimed)
-            .ok_or(ErrorCode::MathOverflow)?;
+    pub fn submit_xp_attestation(
+        ctx: Context<SubmitXpAttestation>,
+        wallet: Pubkey,
+        total_xp: u64,
+        nonce: u64,
+    ) -> Result<()> {
+        let config = &ctx.accounts.config;
+        config.require_not_paused(PAUSE_SYNC_XP)?;
+        require!(config.oracle_threshold == 0, ErrorCode::ThresholdAttestationRequired);
         
-        require!(claimable > 0, ErrorCode::NoSbtToClaim);
+        let epoch = &mut ctx.accounts.epoch;
+        let contributor = &mut ctx.accounts.contributor;
+        let clock = Clock::get()?;
         
-        let remaining_supply = ctx.accounts.config.sbt_total_supply
-            .checked_sub(ctx.accounts.config.sbt_minted)
-            .ok_or(ErrorCode::MathOverflow)?;
+        require!(!epoch.finalized, ErrorCode::EpochFinalized);
+        require!(clock.unix_timestamp >= epoch.start_time, ErrorCode::EpochNotStarted);
+        require!(nonce > contributor.last_attestation_nonce, ErrorCode::StaleAttestationNonce);
+        // Oracle syncs do not consume nonces, so an older attestation could otherwise roll XP back
+        // and let the next sync credit the same XP and SBT again; lowering XP goes through correct_contributor_xp
+        require!(total_xp >= contributor.total_xp, ErrorCode::AttestationBelowCurrentXp);
         
-        let actual_mint = claimable.min(remaining_supply);
-        require!(actual_mint > 0, ErrorCode::SbtSupplyExhausted);
+        let message = xp_attestation_message(&wallet, epoch.epoch_number, total_xp, nonce);
+        verify_oracle_attestation(&ctx.accounts.instructions, &config.oracle, &message)?;
         
-        let bump = ctx.accounts.config.bump;
-        let seeds = &[b"config".as_ref(), &[bump]];
-        let signer = &[&seeds[..]];
+        if ctx.accounts.snapshot.funder == Pubkey::default() {
+            ctx.accounts.snapshot.funder = ctx.accounts.payer.key();
+            epoch.snapshot_count += 1;
+        }
         
-        mint_to(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_2022_program.to_account_info(),
-                MintTo {
-                    mint: ctx.accounts.sbt_mint.to_account_info(),
-                    to: ctx.accounts.contributor_sbt_account.to_account_info(),
-                    authority: ctx.accounts.config.to_account_info(),
-                },
-                signer,
-            ),
-            actual_mint,
+        let sbt_earned = apply_xp_sync(
+            config,
+            epoch,
+            contributor,
+            &mut ctx.accounts.snapshot,
+            wallet,
+            total_xp,
+            ctx.bumps.snapshot,
         )?;
+        contributor.last_attestation_nonce = nonce;
         
-        ctx.accounts.contributor.total_sbt_claimed = ctx.accounts.contributor
-            .total_sbt_claimed
-            .checked_add(actual_mint)
+        emit!(XpSyncedEvent {
+            wallet,
+            github_username: contributor.github_username.clone(),
+            epoch: epoch.epoch_number,
+            total_xp,
+            role_xp: Vec::new(),
+            domain_xp: Vec::new(),
+            skill_xp: Vec::new(),
+            sbt_earned,
+            timestamp: clock.unix_timestamp,
+            is_registered: true,
+        });
+        
+        Ok(())
+    }
+
+    pub fn correct_contributor_xp(
+        ctx: Context<CorrectContributorXp>,
+        wallet: Pubkey,
+        corrected_xp: u64,
+        reason_code: u8,
+    ) -> Result<()> {
+        let config = &ctx.accounts.config;
+        let epoch = &mut ctx.accounts.epoch;
+        let contributor = &mut ctx.accounts.contributor;
+        let authority = ctx.accounts.authority.key();
+        
+        require!(
+            authority == config.oracle || authority == config.admin,
+            ErrorCode::Unauthorized
+        );
+        
+        let previous_xp = contributor.total_xp;
+        require!(corrected_xp < previous_xp, ErrorCode::InvalidXpCorrection);
+        
+        // SBT already minted cannot be burned from a non-transferable account, so only the unclaimed part is revoked.
+        // The ratio is not recorded per sync, so XP credited before an xp_to_sbt_ratio change is clawed back at
+        // the current ratio; admins should correct such XP before changing the ratio.
+        let unclaimed_sbt = contributor
+            .total_sbt_claimable
+            .saturating_sub(contributor.total_sbt_claimed);
+        let sbt_clawed_back = (previous_xp - corrected_xp)
+            .saturating_mul(config.xp_to_sbt_ratio)
+            .min(unclaimed_sbt);
+        
+        contributor.total_xp = corrected_xp;
+        contributor.total_sbt_claimable -= sbt_clawed_back;
+        
+        // The snapshot address is fixed by seeds, so a wallet synced this epoch cannot have its snapshot skipped
+        let snapshot_info = ctx.accounts.snapshot.to_account_info();
+        if snapshot_info.owner == &crate::ID && !snapshot_info.data_is_empty() {
+            require!(!epoch.finalized, ErrorCode::EpochFinalized);
+            let mut snapshot = EpochSnapshot::try_deserialize(&mut &snapshot_info.try_borrow_data()?[..])?;
+            
+            snapshot.baseline_xp = snapshot.baseline_xp.min(corrected_xp);
+            let epoch_xp = match epoch.xp_weighting {
+                XpWeighting::Cumulative => corrected_xp,
+                XpWeighting::PerEpoch => corrected_xp - snapshot.baseline_xp,
+            };
+            
+            epoch.total_xp = epoch
+                .total_xp
+                .checked_sub(snapshot.xp)
+                .ok_or(ErrorCode::MathOverflow)?
+                .checked_add(epoch_xp)
+                .ok_or(ErrorCode::MathOverflow)?;
+            snapshot.xp = epoch_xp;
+            snapshot.sbt_earned = snapshot.sbt_earned.saturating_sub(sbt_clawed_back);
+            snapshot.try_serialize(&mut &mut snapshot_info.try_borrow_mut_data()?[..])?;
+        }
+        
+        emit!(XpCorrectedEvent {
+            wallet,
+            epoch: epoch.epoch_number,
+            previous_xp,
+            corrected_xp,
+            sbt_clawed_back,
+            reason_code,
+            authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        
+        msg!(
+            "Corrected XP for {} from {} to {} (reason {}, {} SBT clawed back)",
+            wallet,
+            previous_xp,
+            corrected_xp,
+            reason_code,
+            sbt_clawed_back
+        );
+        Ok(())
+    }
+
+    pub fn finalize_epoch(
+        ctx: Context<FinalizeEpoch>,
+        epoch_number: u64,
+        merkle_distribution: Option<MerkleDistribution>,
+    ) -> Result<()> {
+        let epoch = &mut ctx.accounts.epoch;
+        let clock = Clock::get()?;
+        
+        require!(!epoch.finalized, ErrorCode::EpochAlreadyFinalized);
+        require!(clock.unix_timestamp >= epoch.end_time, ErrorCode::EpochNotEnded);
+        
+        if let Some(distribution) = merkle_distribution {
+            require!(distribution.root != [0u8; 32], ErrorCode::InvalidMerkleDistribution);
+            require!(
+                distribution.leaf_count > 0 && distribution.leaf_count <= MAX_MERKLE_LEAVES,
+                ErrorCode::InvalidMerkleDistribution
+            );
+            
+            epoch.merkle_root = distribution.root;
+            epoch.merkle_leaf_count = distribution.leaf_count;
+            epoch.claim_bitmap = vec![0u8; claim_bitmap_len(distribution.leaf_count)];
+            
+            msg!(
+                "Epoch {} uses Merkle distribution with {} leaves",
+                epoch_number,
+                distribution.leaf_count
+            );
+        }
+        
+        epoch.finalized = true;
+        
+        msg!(
+            "Epoch {} finalized with {} contributors and {} total XP",
+            epoch_number,
+            epoch.contributor_count,
+            epoch.total_xp
+        );
+        Ok(())
+    }
+
+    pub fn commit_unregistered_xp(
+        ctx: Context<CommitUnregisteredXp>,
+        epoch_number: u64,
+        unregistered_xp_root: [u8; 32],
+        unregistered_total_xp: u64,
+    ) -> Result<()> {
+        let epoch = &mut ctx.accounts.epoch;
+        
+        require!(!epoch.finalized, ErrorCode::EpochFinalized);
+        require!(
+            (unregistered_xp_root == [0u8; 32]) == (unregistered_total_xp == 0),
+            ErrorCode::InvalidMerkleDistribution
+        );
+        
+        epoch.unregistered_xp_root = unregistered_xp_root;
+        epoch.unregistered_total_xp = unregistered_total_xp;
+        
+        msg!(
+            "Committed {} unregistered XP for epoch {}",
+            unregistered_total_xp,
+            epoch_number
+        );
+        Ok(())
+    }
+
+    pub fn register_contributor(ctx: Context<RegisterContributor>, github_username: String) -> Result<()> {
+        require!(
+            !github_username.is_empty() && github_username.len() <= MAX_GITHUB_USERNAME_LEN,
+            ErrorCode::InvalidGithubUsername
+        );
+        
+        let contributor = &mut ctx.accounts.contributor;
+        let clock = Clock::get()?;
+        
+        // The oracle may already have created an empty account while syncing this wallet unregistered
+        require!(contributor.wallet == Pubkey::default(), ErrorCode::AlreadyRegistered);
+        
+        contributor.wallet = ctx.accounts.wallet.key();
+        contributor.github_username = github_username.clone();
+        contributor.total_xp = 0;
+        contributor.total_sbt_claimable = 0;
+        contributor.total_sbt_claimed = 0;
+        contributor.lifetime_usdc_earned = 0;
+        contributor.last_claim_epoch = 0;
+        contributor.registered_at = clock.unix_timestamp;
+        contributor.bump = ctx.bumps.contributor;
+        contributor.version = CONTRIBUTOR_VERSION;
+        contributor.last_attestation_nonce = 0;
+        contributor.last_synced_at = 0;
+        contributor.payout_address = Pubkey::default();
+        contributor.pending_payout_address = Pubkey::default();
+        contributor.payout_effective_at = 0;
+        
+        msg!("Registered contributor {} with GitHub: {} at {}", 
+            contributor.wallet, github_username, contributor.registered_at);
+        Ok(())
+    }
+
+    pub fn update_github_link(ctx: Context<UpdateGithubLink>, new_github_username: String) -> Result<()> {
+        require!(
+            !new_github_username.is_empty() && new_github_username.len() <= MAX_GITHUB_USERNAME_LEN,
+            ErrorCode::InvalidGithubUsername
+        );
+        
+        let contributor = &mut ctx.accounts.contributor;
+        contributor.github_username = new_github_username.clone();
+        
+        msg!("Updated GitHub username to: {}", new_github_username);
+        Ok(())
+    }
+
+    pub fn set_payout_address(ctx: Context<SetPayoutAddress>, payout_address: Pubkey) -> Result<()> {
+        let contributor = &mut ctx.accounts.contributor;
+        let clock = Clock::get()?;
+        
+        // Claims keep paying the current address until the timelock passes, so a compromised
+        // wallet cannot redirect payouts instantly and the owner has time to override the change
+        contributor.settle_payout_address(clock.unix_timestamp);
+        contributor.pending_payout_address = payout_address;
+        contributor.payout_effective_at = clock.unix_timestamp
+            .checked_add(PAYOUT_TIMELOCK_SECS)
             .ok_or(ErrorCode::MathOverflow)?;
         
-        ctx.accounts.config.sbt_minted = ctx.accounts.config
-            .sbt_minted
-            .checked_add(actual_mint)
+        emit!(PayoutAddressChangedEvent {
+            wallet: contributor.wallet,
+            payout_address,
+            effective_at: contributor.payout_effective_at,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        msg!(
+            "Payout address for {} set to {} (effective at {})",
+            contributor.wallet,
+            payout_address,
+            contributor.payout_effective_at
+        );
+        Ok(())
+    }
+
+    pub fn claim_usdc_rewards(ctx: Context<ClaimUsdcRewards>, epoch_number: u64) -> Result<()> {
+        ctx.accounts.config.require_not_paused(PAUSE_CLAIM_USDC)?;
+        
+        let epoch = &mut ctx.accounts.epoch;
+        let snapshot = &mut ctx.accounts.snapshot;
+        let contributor = &mut ctx.accounts.contributor;
+        let clock = Clock::get()?;
+        
+        let reward_amount = snapshot_claim_amount(epoch, snapshot, contributor, clock.unix_timestamp)?;
+        
+        require!(reward_amount > 0, ErrorCode::NoRewardToClaim);
+        epoch.record_claim(reward_amount)?;
+        ctx.accounts.config.release_commitment(reward_amount)?;
+        
+        let received = transfer_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.usdc_vault,
+            &ctx.accounts.usdc_mint,
+            ctx.accounts.contributor_token_account.to_account_info(),
+            &ctx.accounts.config,
+            reward_amount,
+        )?;
+        
+        snapshot.usdc_claimed = true;
+        contributor.lifetime_usdc_earned = contributor
+            .lifetime_usdc_earned
+            .checked_add(received)
             .ok_or(ErrorCode::MathOverflow)?;
+        contributor.last_claim_epoch = epoch_number;
         
         msg!(
-            "Claimed {} SBT tokens (claimable: {}, remaining supply: {})",
-            actual_mint,
-            claimable,
-            remaining_supply
+            "Claimed {} USDC for epoch {} (XP: {}/{}, within 90-day window)",
+            received,
+            epoch_number,
+            snapshot.xp,
+            epoch.total_xp
         );
+        Ok(())
+    }
+
+    pub fn claim_usdc_with_proof(
+        ctx: Context<ClaimUsdcWithProof>,
+        epoch_number: u64,
+        leaf_index: u32,
+        xp: u64,
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        ctx.accounts.config.require_not_paused(PAUSE_CLAIM_USDC)?;
         
-        if actual_mint < claimable {
-            msg!("Warning: {} SBT could not be claimed due to supply cap", claimable - actual_mint);
-        }
+        let epoch = &mut ctx.accounts.epoch;
+        let contributor = &mut ctx.accounts.contributor;
+        let clock = Clock::get()?;
+        
+        require!(epoch.finalized, ErrorCode::EpochNotFinalized);
+        require!(epoch.merkle_leaf_count > 0, ErrorCode::MerkleDistributionNotSet);
+        require!(
+            clock.unix_timestamp <= epoch.end_time + epoch.claim_window_secs,
+            ErrorCode::ClaimWindowExpired
+        );
+        
+        let retroactive_cutoff = contributor.registered_at - epoch.retroactive_window_secs;
+        require!(
+            epoch.end_time >= retroactive_cutoff,
+            ErrorCode::EpochTooOld
+        );
+        
+        require!(leaf_index < epoch.merkle_leaf_count, ErrorCode::InvalidMerkleProof);
+        require!(proof.len() <= MAX_MERKLE_PROOF_LEN, ErrorCode::InvalidMerkleProof);
+        
+        let byte_index = (leaf_index / 8) as usize;
+        let bit_mask = 1u8 << (leaf_index % 8);
+        require!(epoch.claim_bitmap[byte_index] & bit_mask == 0, ErrorCode::AlreadyClaimed);
+        
+        let leaf = merkle_leaf(leaf_index, &ctx.accounts.wallet.key(), xp, amount);
+        require!(
+            verify_merkle_proof(&proof, epoch.merkle_root, leaf),
+            ErrorCode::InvalidMerkleProof
+        );
+        require!(amount > 0, ErrorCode::NoRewardToClaim);
+        epoch.record_claim(amount)?;
+        ctx.accounts.config.release_commitment(amount)?;
+        
+        let received = transfer_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.usdc_vault,
+            &ctx.accounts.usdc_mint,
+            ctx.accounts.contributor_token_account.to_account_info(),
+            &ctx.accounts.config,
+            amount,
+        )?;
+        
+        epoch.claim_bitmap[byte_index] |= bit_mask;
+        contributor.lifetime_usdc_earned = contributor
+            .lifetime_usdc_earned
+            .checked_add(received)
+            .ok_or(ErrorCode::MathOverflow)?;
+        contributor.last_claim_epoch = epoch_number;
         
+        msg!(
+            "Claimed {} USDC for epoch {} via Merkle proof (leaf {}, XP: {})",
+            received,
+            epoch_number,
+            leaf_index,
+            xp
+        );
         Ok(())
     }
 
-    // TODO: Implement batch_claim_rewards using Anchor's AccountLoader or multiple instruction calls
-    // For now, users can call claim_usdc_rewards multiple times for retroactive epochs
-}
+    pub fn claim_retroactive_usdc(
+        ctx: Context<ClaimRetroactiveUsdc>,
+        epoch_number: u64,
+        xp: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        ctx.accounts.config.require_not_paused(PAUSE_CLAIM_USDC)?;
+        
+        let epoch = &mut ctx.accounts.epoch;
+        let snapshot = &mut ctx.accounts.snapshot;
+        let contributor = &mut ctx.accounts.contributor;
+        let wallet = ctx.accounts.wallet.key();
+        let clock = Clock::get()?;
+        
+        require!(epoch.finalized, ErrorCode::EpochNotFinalized);
+        // Merkle leaves are owed the whole reward, so retroactive shares would be paid out of them
+        require!(epoch.merkle_leaf_count == 0, ErrorCode::MerkleDistributionActive);
+        require!(epoch.unregistered_total_xp > 0, ErrorCode::NoUnregisteredXpCommitted);
+        require!(
+            clock.unix_timestamp <= epoch.end_time + epoch.retroactive_window_secs,
+            ErrorCode::ClaimWindowExpired
+        );
+        require!(
+            epoch.end_time >= contributor.registered_at - epoch.retroactive_window_secs,
+            ErrorCode::EpochTooOld
+        );
+        
+        require!(
+            snapshot.contributor == Pubkey::default() && !snapshot.usdc_claimed,
+            ErrorCode::AlreadyClaimed
+        );
+        require!(proof.len() <= MAX_MERKLE_PROOF_LEN, ErrorCode::InvalidMerkleProof);
+        require!(
+            verify_merkle_proof(&proof, epoch.unregistered_xp_root, unregistered_xp_leaf(&wallet, xp)),
+            ErrorCode::InvalidMerkleProof
+        );
+        
+        let reward_amount = epoch.reward_share(xp)?;
+        require!(reward_amount > 0, ErrorCode::NoRewardToClaim);
+        epoch.record_claim(reward_amount)?;
+        ctx.accounts.config.release_commitment(reward_amount)?;
+        
+        let received = transfer_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.usdc_vault,
+            &ctx.accounts.usdc_mint,
+            ctx.accounts.contributor_token_account.to_account_info(),
+            &ctx.accounts.config,
+            reward_amount,
+        )?;
+        
+        snapshot.contributor = wallet;
+        snapshot.epoch = epoch_number;
+        snapshot.xp = xp;
+        snapshot.usdc_claimed = true;
+        snapshot.sbt_earned = 0;
+        snapshot.bump = ctx.bumps.snapshot;
+        snapshot.version = SNAPSHOT_VERSION;
+        if snapshot.funder == Pubkey::default() {
+            snapshot.funder = wallet;
+            epoch.snapshot_count += 1;
+        }
+        
+        contributor.lifetime_usdc_earned = contributor
+            .lifetime_usdc_earned
+            .checked_add(received)
+            .ok_or(ErrorCode::MathOverflow)?;
+        contributor.last_claim_epoch = epoch_number;
+        
+        msg!(
+            "Retroactively claimed {} USDC for epoch {} (unregistered XP: {})",
+            received,
+            epoch_number,
+            xp
+        );
+        Ok(())
+    }
 
-#[derive(Accounts)]
-#[instruction(oracle: Pubkey, usdc_mint: Pubkey, sbt_mint: Pubkey)]
-pub struct Initialize<'info> {
+    pub fn close_snapshot(ctx: Context<CloseSnapshot>, epoch_number: u64, wallet: Pubkey) -> Result<()> {
+        let epoch = &mut ctx.accounts.epoch;
+        let snapshot = &ctx.accounts.snapshot;
+        let authority = ctx.accounts.authority.key();
+        let clock = Clock::get()?;
+        
+        let is_contributor = snapshot.contributor != Pubkey::default() && authority == snapshot.contributor;
+        require!(
+            is_contributor || authority == ctx.accounts.config.oracle,
+            ErrorCode::Unauthorized
+        );
+        require!(epoch.finalized, ErrorCode::EpochNotFinalized);
+        
+        if clock.unix_timestamp <= epoch.claim_deadline() {
+            // Retroactive claims rely on the snapshot existing to block replays until the window ends
+            require!(
+                is_contributor && snapshot.usdc_claimed && epoch.unregistered_total_xp == 0,
+                ErrorCode::ClaimWindowNotExpired
+            );
+        }
+        
+        epoch.snapshot_count = epoch.snapshot_count.saturating_sub(1);
+        
+        msg!(
+            "Closed snapshot for {} in epoch {}, rent returned to {}",
+            wallet,
+            epoch_number,
+            snapshot.funder
+        );
+        Ok(())
+    }
+
+    pub fn close_epoch(ctx: Context<CloseEpoch>, epoch_number: u64) -> Result<()> {
+        let epoch = &ctx.accounts.epoch;
+        let clock = Clock::get()?;
+        
+        require!(epoch_number < ctx.accounts.config.current_epoch, ErrorCode::EpochStillActive);
+        epoch.require_closable(clock.unix_timestamp)?;
+        
+        emit!(EpochArchivedEvent {
+            epoch: epoch_number,
+            total_xp: epoch.total_xp,
+            unregistered_total_xp: epoch.unregistered_total_xp,
+            contributor_count: epoch.contributor_count,
+            usdc_reward_amount: epoch.usdc_reward_amount,
+            usdc_distributed: epoch.usdc_claimed_total,
+            usdc_swept: epoch.usdc_swept,
+            timestamp: clock.unix_timestamp,
+        });
+        
+        msg!("Archived and closed epoch {}", epoch_number);
+        Ok(())
+    }
+
+    pub fn claim_sbt_tokens(ctx: Context<ClaimSbtTokens>) -> Result<()> {
+        ctx.accounts.config.require_not_paused(PAUSE_CLAIM_SBT)?;
+        
+        let claimable = ctx.accounts.contributor.total_sbt_claimable
+            .checked_sub(ctx.accounts.contributor.total_sbt_claimed)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        require!(claimable > 0, ErrorCode::NoSbtToClaim);
+        
+        let remaining_supply = ctx.accounts.config.sbt_total_supply
+            .checked_sub(ctx.accounts.config.sbt_minted)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        let actual_mint = claimable.min(remaining_supply);
+        require!(actual_mint > 0, ErrorCode::SbtSupplyExhausted);
+        
+        let bump = ctx.accounts.config.bump;
+        let seeds = &[b"config".as_ref(), &[bump]];
+        let signer = &[&seeds[..]];
+        
+        mint_to(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_2022_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.sbt_mint.to_account_info(),
+                    to: ctx.accounts.contributor_sbt_account.to_account_info(),
+                    authority: ctx.accounts.config.to_account_info(),
+                },
+                signer,
+            ),
+            actual_mint,
+        )?;
+        
+        ctx.accounts.contributor.total_sbt_claimed = ctx.accounts.contributor
+            .total_sbt_claimed
+            .checked_add(actual_mint)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        ctx.accounts.config.sbt_minted = ctx.accounts.config
+            .sbt_minted
+            .checked_add(actual_mint)
+            .ok_or(ErrorCode::MathOverflow)?;
+        
+        msg!(
+            "Claimed {} SBT tokens (claimable: {}, remaining supply: {})",
+            actual_mint,
+            claimable,
+            remaining_supply
+        );
+        
+        if actual_mint < claimable {
+            msg!("Warning: {} SBT could not be claimed due to supply cap", claimable - actual_mint);
+        }
+        
+        Ok(())
+    }
+
+    pub fn batch_claim_rewards<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchClaimRewards<'info>>,
+        epoch_numbers: Vec<u64>,
+    ) -> Result<()> {
+        ctx.accounts.config.require_not_paused(PAUSE_CLAIM_USDC)?;
+        require!(
+            !epoch_numbers.is_empty() && epoch_numbers.len() <= MAX_BATCH_CLAIM_EPOCHS,
+            ErrorCode::InvalidBatchSize
+        );
+        require!(
+            ctx.remaining_accounts.len() == epoch_numbers.len() * 2,
+            ErrorCode::InvalidBatchAccount
+        );
+        
+        let contributor = &mut ctx.accounts.contributor;
+        let wallet = ctx.accounts.wallet.key();
+        let clock = Clock::get()?;
+        
+        let mut total_reward = 0u64;
+        let mut last_epoch = contributor.last_claim_epoch;
+        
+        for (&epoch_number, accounts) in epoch_numbers.iter().zip(ctx.remaining_accounts.chunks(2)) {
+            let epoch_info = &accounts[0];
+            let snapshot_info = &accounts[1];
+            let epoch_seed = epoch_number.to_le_bytes();
+            
+            require!(
+                epoch_info.owner == &crate::ID && snapshot_info.owner == &crate::ID,
+                ErrorCode::InvalidBatchAccount
+            );
+            
+            let mut epoch = RewardEpoch::try_deserialize(&mut &epoch_info.try_borrow_data()?[..])?;
+            let expected_epoch = Pubkey::create_program_address(
+                &[b"epoch", epoch_seed.as_ref(), &[epoch.bump]],
+                &crate::ID,
+            )
+            .map_err(|_| error!(ErrorCode::InvalidBatchAccount))?;
+            require_keys_eq!(epoch_info.key(), expected_epoch, ErrorCode::InvalidBatchAccount);
+            
+            let mut snapshot = EpochSnapshot::try_deserialize(&mut &snapshot_info.try_borrow_data()?[..])?;
+            let expected_snapshot = Pubkey::create_program_address(
+                &[b"snapshot", epoch_seed.as_ref(), wallet.as_ref(), &[snapshot.bump]],
+                &crate::ID,
+            )
+            .map_err(|_| error!(ErrorCode::InvalidBatchAccount))?;
+            require_keys_eq!(snapshot_info.key(), expected_snapshot, ErrorCode::InvalidBatchAccount);
+            require_keys_eq!(snapshot.contributor, wallet, ErrorCode::InvalidBatchAccount);
+            
+            let reward_amount = snapshot_claim_amount(
+                &epoch,
+                &snapshot,
+                contributor,
+                clock.unix_timestamp,
+            )?;
+            epoch.record_claim(reward_amount)?;
+            
+            snapshot.usdc_claimed = true;
+            snapshot.try_serialize(&mut &mut snapshot_info.try_borrow_mut_data()?[..])?;
+            epoch.try_serialize(&mut &mut epoch_info.try_borrow_mut_data()?[..])?;
+            
+            total_reward = total_reward
+                .checked_add(reward_amount)
+                .ok_or(ErrorCode::MathOverflow)?;
+            last_epoch = last_epoch.max(epoch_number);
+            
+            msg!("Epoch {}: {} USDC (XP: {})", epoch_number, reward_amount, snapshot.xp);
+        }
+        
+        require!(total_reward > 0, ErrorCode::NoRewardToClaim);
+        ctx.accounts.config.release_commitment(total_reward)?;
+        
+        let received = transfer_from_vault(
+            &ctx.accounts.token_program,
+            &ctx.accounts.usdc_vault,
+            &ctx.accounts.usdc_mint,
+            ctx.accounts.contributor_token_account.to_account_info(),
+            &ctx.accounts.config,
+            total_reward,
+        )?;
+        
+        contributor.lifetime_usdc_earned = contributor
+            .lifetime_usdc_earned
+            .checked_add(received)
+            .ok_or(ErrorCode::MathOverflow)?;
+        contributor.last_claim_epoch = last_epoch;
+        
+        msg!("Batch claimed {} USDC across {} epochs", received, epoch_numbers.len());
+        Ok(())
+    }
+
+    pub fn sweep_expired_epoch(
+        ctx: Context<SweepExpiredEpoch>,
+        epoch_number: u64,
+        destination: SweepDestination,
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let epoch = &mut ctx.accounts.epoch;
+        let remainder = epoch.sweep(Clock::get()?.unix_timestamp)?;
+        
+        match destination {
+            SweepDestination::Rollover => {
+                let next_epoch = ctx
+                    .accounts
+                    .next_epoch
+                    .as_mut()
+                    .ok_or(ErrorCode::MissingSweepDestination)?;
+                // The remainder stays committed, it just moves to the next epoch
+                next_epoch.add_rollover(remainder)?;
+                
+                msg!(
+                    "Rolled {} unclaimed USDC from epoch {} into epoch {}",
+                    remainder,
+                    epoch_number,
+                    next_epoch.epoch_number
+                );
+            }
+            SweepDestination::Treasury => {
+                require!(config.treasury != Pubkey::default(), ErrorCode::TreasuryNotSet);
+                let treasury_token_account = ctx
+                    .accounts
+                    .treasury_token_account
+                    .as_ref()
+                    .ok_or(ErrorCode::MissingSweepDestination)?;
+                
+                config.release_commitment(remainder)?;
+                
+                if remainder > 0 {
+                    transfer_from_vault(
+                        &ctx.accounts.token_program,
+                        &ctx.accounts.usdc_vault,
+                        &ctx.accounts.usdc_mint,
+                        treasury_token_account.to_account_info(),
+                        config,
+                        remainder,
+                    )?;
+                }
+                
+                msg!(
+                    "Swept {} unclaimed USDC from epoch {} to treasury {}",
+                    remainder,
+                    epoch_number,
+                    config.treasury
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub fn migrate_config(ctx: Context<MigrateConfig>) -> Result<()> {
+        let config_info = ctx.accounts.config.to_account_info();
+        require!(config_info.data_len() < Config::SPACE, ErrorCode::AlreadyMigrated);
+        
+        grow_account(
+            &config_info,
+            &ctx.accounts.admin.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            Config::SPACE,
+        )?;
+        
+        let mut config = Config::try_deserialize(&mut &config_info.try_borrow_data()?[..])?;
+        require_keys_eq!(config.admin, ctx.accounts.admin.key(), ErrorCode::Unauthorized);
+        
+        config.upgrade_from_v1();
+        config.try_serialize(&mut &mut config_info.try_borrow_mut_data()?[..])?;
+        
+        msg!("Migrated config to version {}", CONFIG_VERSION);
+        Ok(())
+    }
+
+    pub fn migrate_contributor(ctx: Context<MigrateContributor>, wallet: Pubkey) -> Result<()> {
+        let contributor_info = ctx.accounts.contributor.to_account_info();
+        require!(contributor_info.data_len() < Contributor::SPACE, ErrorCode::AlreadyMigrated);
+        
+        grow_account(
+            &contributor_info,
+            &ctx.accounts.admin.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            Contributor::SPACE,
+        )?;
+        
+        let mut contributor = Contributor::try_deserialize(&mut &contributor_info.try_borrow_data()?[..])?;
+        contributor.upgrade_from_v1();
+        contributor.try_serialize(&mut &mut contributor_info.try_borrow_mut_data()?[..])?;
+        
+        msg!("Migrated contributor {} to version {}", wallet, CONTRIBUTOR_VERSION);
+        Ok(())
+    }
+
+    /// v1 epochs did not track payouts or open snapshots, so the admin supplies both, counted
+    /// off-chain from the epoch's snapshot accounts.
+    pub fn migrate_epoch(
+        ctx: Context<MigrateEpoch>,
+        epoch_number: u64,
+        usdc_claimed_total: u64,
+        snapshot_count: u32,
+    ) -> Result<()> {
+        let epoch_info = ctx.accounts.epoch.to_account_info();
+        require!(epoch_info.data_len() < RewardEpoch::space(0), ErrorCode::AlreadyMigrated);
+        
+        grow_account(
+            &epoch_info,
+            &ctx.accounts.admin.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            RewardEpoch::space(0),
+        )?;
+        
+        let mut epoch = RewardEpoch::try_deserialize(&mut &epoch_info.try_borrow_data()?[..])?;
+        epoch.upgrade_from_v1(usdc_claimed_total, snapshot_count)?;
+        epoch.try_serialize(&mut &mut epoch_info.try_borrow_mut_data()?[..])?;
+        
+        // Unclaimed rewards stay reserved in the vault until the epoch is swept
+        let config = &mut ctx.accounts.config;
+        config.usdc_committed = config
+            .usdc_committed
+            .checked_add(epoch.usdc_reward_amount - epoch.usdc_claimed_total)
+            .ok_or(ErrorCode::MathOverflow)?;
+        config.last_epoch_end = config.last_epoch_end.max(epoch.end_time);
+        
+        msg!(
+            "Migrated epoch {} to version {} ({} USDC still committed)",
+            epoch_number,
+            EPOCH_VERSION,
+            epoch.usdc_reward_amount - epoch.usdc_claimed_total
+        );
+        Ok(())
+    }
+
+    pub fn migrate_snapshot(ctx: Context<MigrateSnapshot>, epoch_number: u64, wallet: Pubkey) -> Result<()> {
+        let snapshot_info = ctx.accounts.snapshot.to_account_info();
+        require!(snapshot_info.data_len() < EpochSnapshot::SPACE, ErrorCode::AlreadyMigrated);
+        
+        grow_account(
+            &snapshot_info,
+            &ctx.accounts.admin.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            EpochSnapshot::SPACE,
+        )?;
+        
+        let mut snapshot = EpochSnapshot::try_deserialize(&mut &snapshot_info.try_borrow_data()?[..])?;
+        snapshot.upgrade_from_v1(ctx.accounts.config.oracle);
+        snapshot.try_serialize(&mut &mut snapshot_info.try_borrow_mut_data()?[..])?;
+        
+        msg!(
+            "Migrated snapshot for {} in epoch {} to version {}",
+            wallet,
+            epoch_number,
+            SNAPSHOT_VERSION
+        );
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(oracle: Pubkey, usdc_mint: Pubkey, sbt_mint: Pubkey)]
+pub struct Initialize<'info> {
+    #[account(
+        init,
+        payer = admin,
+        space = Config::SPACE,
+        seeds = [b"config"],
+        bump
+    )]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        init,
+        payer = admin,
+        associated_token::mint = usdc_mint_account,
+        associated_token::authority = config,
+        associated_token::token_program = token_program,
+    )]
+    pub usdc_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        address = usdc_mint @ ErrorCode::InvalidUsdcMint,
+        mint::token_program = token_program
+    )]
+    pub usdc_mint_account: InterfaceAccount<'info, Mint>,
+    
+    pub sbt_mint_account: Option<InterfaceAccount<'info, Mint>>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreateSbtMint<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin,
+        constraint = config.sbt_mint == Pubkey::default() @ ErrorCode::SbtMintAlreadySet
+    )]
+    pub config: Account<'info, Config>,
+    
+    /// CHECK: Created and initialized here as a Token-2022 mint at the program's SBT mint PDA
+    #[account(mut, seeds = [b"sbt_mint"], bump)]
+    pub sbt_mint: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    pub token_2022_program: Program<'info, Token2022>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateOracle<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub admin: Signer<'info>,
+    
+    /// CHECK: New oracle address
+    pub new_oracle: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = admin
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = config.pending_admin != Pubkey::default() @ ErrorCode::NotPendingAdmin,
+        constraint = config.pending_admin == pending_admin.key() @ ErrorCode::NotPendingAdmin
+    )]
+    pub config: Account<'info, Config>,
+    
+    pub pending_admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FundUsdcPool<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        constraint = usdc_vault.key() == config.usdc_vault
+    )]
+    pub usdc_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        address = config.usdc_mint @ ErrorCode::InvalidUsdcMint,
+        mint::token_program = token_program
+    )]
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(mut)]
+    pub funder: Signer<'info>,
+    
+    #[account(
+        mut,
+        token::mint = usdc_mint,
+        token::authority = funder,
+        token::token_program = token_program
+    )]
+    pub funder_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct CreateEpoch<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        has_one = oracle
+    )]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        init,
+        payer = oracle,
+        space = RewardEpoch::space(0),
+        seeds = [b"epoch", (config.current_epoch + 1).to_le_bytes().as_ref()],
+        bump
+    )]
+    pub epoch: Account<'info, RewardEpoch>,
+    
+    #[account(
+        mut,
+        seeds = [b"epoch", config.current_epoch.to_le_bytes().as_ref()],
+        bump = previous_epoch.bump
+    )]
+    pub previous_epoch: Option<Account<'info, RewardEpoch>>,
+    
+    #[account(constraint = usdc_vault.key() == config.usdc_vault)]
+    pub usdc_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub oracle: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey, github_username: String, total_xp: u64, role_xp_data: Vec<XpCategory>, domain_xp_data: Vec<XpCategory>, skill_xp_data: Vec<XpCategory>)]
+pub struct SyncContributorXp<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = oracle)]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"epoch", config.current_epoch.to_le_bytes().as_ref()],
+        bump = epoch.bump
+    )]
+    pub epoch: Account<'info, RewardEpoch>,
+    
+    #[account(
+        init_if_needed,
+        payer = oracle,
+        space = Contributor::SPACE,
+        seeds = [b"contributor", wallet.as_ref()],
+        bump
+    )]
+    pub contributor: Account<'info, Contributor>,
+    
+    #[account(
+        init_if_needed,
+        payer = oracle,
+        space = EpochSnapshot::SPACE,
+        seeds = [b"snapshot", config.current_epoch.to_le_bytes().as_ref(), wallet.as_ref()],
+        bump
+    )]
+    pub snapshot: Account<'info, EpochSnapshot>,
+    
+    #[account(mut)]
+    pub oracle: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BatchSyncContributorXp<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = oracle)]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"epoch", config.current_epoch.to_le_bytes().as_ref()],
+        bump = epoch.bump
+    )]
+    pub epoch: Account<'info, RewardEpoch>,
+    
+    #[account(mut)]
+    pub oracle: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey, total_xp: u64)]
+pub struct ProposeXpUpdate<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        seeds = [b"epoch", config.current_epoch.to_le_bytes().as_ref()],
+        bump = epoch.bump
+    )]
+    pub epoch: Account<'info, RewardEpoch>,
+    
+    #[account(
+        init,
+        payer = proposer,
+        space = XpProposal::SPACE,
+        seeds = [
+            b"xp_proposal",
+            config.current_epoch.to_le_bytes().as_ref(),
+            wallet.as_ref(),
+            total_xp.to_le_bytes().as_ref()
+        ],
+        bump
+    )]
+    pub proposal: Account<'info, XpProposal>,
+    
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey, total_xp: u64)]
+pub struct ApproveXpUpdate<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"epoch", config.current_epoch.to_le_bytes().as_ref()],
+        bump = epoch.bump
+    )]
+    pub epoch: Account<'info, RewardEpoch>,
+    
+    #[account(
+        mut,
+        seeds = [
+            b"xp_proposal",
+            config.current_epoch.to_le_bytes().as_ref(),
+            wallet.as_ref(),
+            total_xp.to_le_bytes().as_ref()
+        ],
+        bump = proposal.bump,
+        has_one = proposer
+    )]
+    pub proposal: Account<'info, XpProposal>,
+    
+    #[account(
+        mut,
+        seeds = [b"contributor", wallet.as_ref()],
+        bump = contributor.bump,
+        constraint = contributor.wallet == wallet @ ErrorCode::ContributorNotRegistered
+    )]
+    pub contributor: Account<'info, Contributor>,
+    
+    /// CHECK: Created on the approval that reaches the threshold; the address is checked against its seeds
+    #[account(mut)]
+    pub snapshot: UncheckedAccount<'info>,
+    
+    /// CHECK: Receives the proposal rent once the update is applied
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub approver: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch_number: u64, wallet: Pubkey, total_xp: u64)]
+pub struct CancelXpProposal<'info> {
+    #[account(
+        mut,
+        close = proposer,
+        seeds = [
+            b"xp_proposal",
+            epoch_number.to_le_bytes().as_ref(),
+            wallet.as_ref(),
+            total_xp.to_le_bytes().as_ref()
+        ],
+        bump = proposal.bump,
+        has_one = proposer
+    )]
+    pub proposal: Account<'info, XpProposal>,
+    
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct SubmitXpAttestation<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"epoch", config.current_epoch.to_le_bytes().as_ref()],
+        bump = epoch.bump
+    )]
+    pub epoch: Account<'info, RewardEpoch>,
+    
+    #[account(
+        mut,
+        seeds = [b"contributor", wallet.as_ref()],
+        bump = contributor.bump,
+        constraint = contributor.wallet == wallet @ ErrorCode::ContributorNotRegistered
+    )]
+    pub contributor: Account<'info, Contributor>,
+    
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = EpochSnapshot::SPACE,
+        seeds = [b"snapshot", config.current_epoch.to_le_bytes().as_ref(), wallet.as_ref()],
+        bump
+    )]
+    pub snapshot: Account<'info, EpochSnapshot>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// CHECK: Instructions sysvar, used to read the preceding Ed25519 verification
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct CorrectContributorXp<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"epoch", config.current_epoch.to_le_bytes().as_ref()],
+        bump = epoch.bump
+    )]
+    pub epoch: Account<'info, RewardEpoch>,
+    
+    #[account(
+        mut,
+        seeds = [b"contributor", wallet.as_ref()],
+        bump = contributor.bump,
+        constraint = contributor.wallet == wallet @ ErrorCode::ContributorNotRegistered
+    )]
+    pub contributor: Account<'info, Contributor>,
+    
+    /// CHECK: The wallet's snapshot PDA for the current epoch; adjusted only when it has been created
+    #[account(
+        mut,
+        seeds = [b"snapshot", config.current_epoch.to_le_bytes().as_ref(), wallet.as_ref()],
+        bump
+    )]
+    pub snapshot: UncheckedAccount<'info>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch_number: u64, merkle_distribution: Option<MerkleDistribution>)]
+pub struct FinalizeEpoch<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = oracle)]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"epoch", epoch_number.to_le_bytes().as_ref()],
+        bump = epoch.bump,
+        realloc = RewardEpoch::space(merkle_distribution.as_ref().map_or(0, |d| d.leaf_count)),
+        realloc::payer = oracle,
+        realloc::zero = true
+    )]
+    pub epoch: Account<'info, RewardEpoch>,
+    
+    #[account(mut)]
+    pub oracle: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch_number: u64)]
+pub struct CommitUnregisteredXp<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = oracle)]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"epoch", epoch_number.to_le_bytes().as_ref()],
+        bump = epoch.bump
+    )]
+    pub epoch: Account<'info, RewardEpoch>,
+    
+    pub oracle: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterContributor<'info> {
+    #[account(
+        init_if_needed,
+        payer = wallet,
+        space = Contributor::SPACE,
+        seeds = [b"contributor", wallet.key().as_ref()],
+        bump
+    )]
+    pub contributor: Account<'info, Contributor>,
+    
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateGithubLink<'info> {
+    #[account(
+        mut,
+        seeds = [b"contributor", wallet.key().as_ref()],
+        bump = contributor.bump,
+        has_one = wallet
+    )]
+    pub contributor: Account<'info, Contributor>,
+    
+    pub wallet: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetPayoutAddress<'info> {
+    #[account(
+        mut,
+        seeds = [b"contributor", wallet.key().as_ref()],
+        bump = contributor.bump,
+        has_one = wallet
+    )]
+    pub contributor: Account<'info, Contributor>,
+    
+    pub wallet: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch_number: u64)]
+pub struct ClaimUsdcRewards<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"epoch", epoch_number.to_le_bytes().as_ref()],
+        bump = epoch.bump
+    )]
+    pub epoch: Account<'info, RewardEpoch>,
+    
+    #[account(
+        mut,
+        seeds = [b"snapshot", epoch_number.to_le_bytes().as_ref(), wallet.key().as_ref()],
+        bump = snapshot.bump,
+        constraint = snapshot.contributor == wallet.key()
+    )]
+    pub snapshot: Account<'info, EpochSnapshot>,
+    
+    #[account(
+        mut,
+        seeds = [b"contributor", wallet.key().as_ref()],
+        bump = contributor.bump,
+        has_one = wallet
+    )]
+    pub contributor: Account<'info, Contributor>,
+    
+    #[account(
+        mut,
+        constraint = usdc_vault.key() == config.usdc_vault
+    )]
+    pub usdc_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        address = config.usdc_mint @ ErrorCode::InvalidUsdcMint,
+        mint::token_program = token_program
+    )]
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: Owner of the claim destination, the contributor's payout address or the wallet itself
+    #[account(address = contributor.payout_owner(Clock::get()?.unix_timestamp) @ ErrorCode::InvalidPayoutAddress)]
+    pub payout_owner: UncheckedAccount<'info>,
+    
+    #[account(
+        init_if_needed,
+        payer = wallet,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = payout_owner,
+        associated_token::token_program = token_program
+    )]
+    pub contributor_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch_number: u64)]
+pub struct ClaimUsdcWithProof<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"epoch", epoch_number.to_le_bytes().as_ref()],
+        bump = epoch.bump
+    )]
+    pub epoch: Account<'info, RewardEpoch>,
+    
+    #[account(
+        mut,
+        seeds = [b"contributor", wallet.key().as_ref()],
+        bump = contributor.bump,
+        has_one = wallet
+    )]
+    pub contributor: Account<'info, Contributor>,
+    
+    #[account(
+        mut,
+        constraint = usdc_vault.key() == config.usdc_vault
+    )]
+    pub usdc_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        address = config.usdc_mint @ ErrorCode::InvalidUsdcMint,
+        mint::token_program = token_program
+    )]
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: Owner of the claim destination, the contributor's payout address or the wallet itself
+    #[account(address = contributor.payout_owner(Clock::get()?.unix_timestamp) @ ErrorCode::InvalidPayoutAddress)]
+    pub payout_owner: UncheckedAccount<'info>,
+    
+    #[account(
+        init_if_needed,
+        payer = wallet,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = payout_owner,
+        associated_token::token_program = token_program
+    )]
+    pub contributor_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch_number: u64)]
+pub struct ClaimRetroactiveUsdc<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"epoch", epoch_number.to_le_bytes().as_ref()],
+        bump = epoch.bump
+    )]
+    pub epoch: Account<'info, RewardEpoch>,
+    
+    #[account(
+        init_if_needed,
+        payer = wallet,
+        space = EpochSnapshot::SPACE,
+        seeds = [b"snapshot", epoch_number.to_le_bytes().as_ref(), wallet.key().as_ref()],
+        bump
+    )]
+    pub snapshot: Account<'info, EpochSnapshot>,
+    
+    #[account(
+        mut,
+        seeds = [b"contributor", wallet.key().as_ref()],
+        bump = contributor.bump,
+        has_one = wallet
+    )]
+    pub contributor: Account<'info, Contributor>,
+    
+    #[account(
+        mut,
+        constraint = usdc_vault.key() == config.usdc_vault
+    )]
+    pub usdc_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        address = config.usdc_mint @ ErrorCode::InvalidUsdcMint,
+        mint::token_program = token_program
+    )]
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: Owner of the claim destination, the contributor's payout address or the wallet itself
+    #[account(address = contributor.payout_owner(Clock::get()?.unix_timestamp) @ ErrorCode::InvalidPayoutAddress)]
+    pub payout_owner: UncheckedAccount<'info>,
+    
+    #[account(
+        init_if_needed,
+        payer = wallet,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = payout_owner,
+        associated_token::token_program = token_program
+    )]
+    pub contributor_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct BatchClaimRewards<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"contributor", wallet.key().as_ref()],
+        bump = contributor.bump,
+        has_one = wallet
+    )]
+    pub contributor: Account<'info, Contributor>,
+    
+    #[account(
+        mut,
+        constraint = usdc_vault.key() == config.usdc_vault
+    )]
+    pub usdc_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        address = config.usdc_mint @ ErrorCode::InvalidUsdcMint,
+        mint::token_program = token_program
+    )]
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+    
+    /// CHECK: Owner of the claim destination, the contributor's payout address or the wallet itself
+    #[account(address = contributor.payout_owner(Clock::get()?.unix_timestamp) @ ErrorCode::InvalidPayoutAddress)]
+    pub payout_owner: UncheckedAccount<'info>,
+    
+    #[account(
+        init_if_needed,
+        payer = wallet,
+        associated_token::mint = usdc_mint,
+        associated_token::authority = payout_owner,
+        associated_token::token_program = token_program
+    )]
+    pub contributor_token_account: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch_number: u64)]
+pub struct SweepExpiredEpoch<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+        constraint = authority.key() == config.admin || authority.key() == config.oracle @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"epoch", epoch_number.to_le_bytes().as_ref()],
+        bump = epoch.bump
+    )]
+    pub epoch: Account<'info, RewardEpoch>,
+    
+    #[account(
+        mut,
+        seeds = [b"epoch", config.current_epoch.to_le_bytes().as_ref()],
+        bump = next_epoch.bump,
+        constraint = next_epoch.key() != epoch.key() @ ErrorCode::MissingSweepDestination
+    )]
+    pub next_epoch: Option<Account<'info, RewardEpoch>>,
+    
+    #[account(
+        mut,
+        constraint = usdc_vault.key() == config.usdc_vault
+    )]
+    pub usdc_vault: InterfaceAccount<'info, TokenAccount>,
+    
+    #[account(
+        address = config.usdc_mint @ ErrorCode::InvalidUsdcMint,
+        mint::token_program = token_program
+    )]
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+    
+    #[account(
+        mut,
+        constraint = treasury_token_account.owner == config.treasury,
+        constraint = treasury_token_account.mint == config.usdc_mint
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+    
+    pub authority: Signer<'info>,
+    
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch_number: u64, wallet: Pubkey)]
+pub struct CloseSnapshot<'info> {
+    #[account(seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"epoch", epoch_number.to_le_bytes().as_ref()],
+        bump = epoch.bump
+    )]
+    pub epoch: Account<'info, RewardEpoch>,
+    
+    #[account(
+        mut,
+        seeds = [b"snapshot", epoch_number.to_le_bytes().as_ref(), wallet.as_ref()],
+        bump,
+        close = funder
+    )]
+    pub snapshot: Account<'info, EpochSnapshot>,
+    
+    /// CHECK: Rent recipient, verified against snapshot.funder
+    #[account(mut, address = snapshot.funder)]
+    pub funder: UncheckedAccount<'info>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoch_number: u64)]
+pub struct CloseEpoch<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = oracle)]
+    pub config: Account<'info, Config>,
+    
+    #[account(
+        mut,
+        seeds = [b"epoch", epoch_number.to_le_bytes().as_ref()],
+        bump = epoch.bump,
+        close = oracle
+    )]
+    pub epoch: Account<'info, RewardEpoch>,
+    
+    #[account(mut)]
+    pub oracle: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimSbtTokens<'info> {
     #[account(
-        init,
-        payer = admin,
-        space = 8 + 32 + 32 + 32 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 1,
+        mut,
         seeds = [b"config"],
-        bump
+        bump = config.bump
     )]
     pub config: Account<'info, Config>,
     
     #[account(
-        init,
-        payer = admin,
-        associated_token::mint = usdc_mint_account,
-        associated_token::authority = config,
+        mut,
+        seeds = [b"contributor", wallet.key().as_ref()],
+        bump = contributor.bump,
+        has_one = wallet
+    )]
+    pub contributor: Account<'info, Contributor>,
+    
+    #[account(
+        mut,
+        constraint = sbt_mint.key() == config.sbt_mint,
+        mint::token_program = token_2022_program
     )]
-    pub usdc_vault: Account<'info, TokenAccount>,
+    pub sbt_mint: InterfaceAccount<'info, Mint>,
     
-    /// CHECK: USDC mint verified by ATA
-    pub usdc_mint_account: AccountInfo<'info>,
+    #[account(
+        init_if_needed,
+        payer = wallet,
+        associated_token::mint = sbt_mint,
+        associated_token::authority = wallet,
+        associated_token::token_program = token_2022_program
+    )]
+    pub contributor_sbt_account: InterfaceAccount<'info, TokenAccount>,
     
     #[account(mut)]
-    pub admin: Signer<'info>,
+    pub wallet: Signer<'info>,
     
-    pub token_program: Program<'info, Token>,
+    pub token_2022_program: Program<'info, Token2022>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct UpdateOracle<'info> {
-    #[account(
-        mut,
-        seeds = [b"config"],
-        bump = config.bump,
-        has_one = admin
-    )]
-    pub config: Account<'info, Config>,
+pub struct MigrateConfig<'info> {
+    /// CHECK: v1 configs are too short to deserialize as `Config`; the handler reallocs first and then checks the admin
+    #[account(mut, seeds = [b"config"], bump)]
+    pub config: UncheckedAccount<'info>,
     
+    #[account(mut)]
     pub admin: Signer<'info>,
     
-    /// CHECK: New oracle address
-    pub new_oracle: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct FundUsdcPool<'info> {
-    #[account(seeds = [b"config"], bump = config.bump)]
+#[instruction(wallet: Pubkey)]
+pub struct MigrateContributor<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
     pub config: Account<'info, Config>,
     
-    #[account(
-        mut,
-        constraint = usdc_vault.key() == config.usdc_vault
-    )]
-    pub usdc_vault: Account<'info, TokenAccount>,
-    
-    #[account(mut)]
-    pub funder: Signer<'info>,
+    /// CHECK: v1 contributors are too short to deserialize as `Contributor`; the address is checked by seeds
+    #[account(mut, seeds = [b"contributor", wallet.as_ref()], bump)]
+    pub contributor: UncheckedAccount<'info>,
     
     #[account(mut)]
-    pub funder_token_account: Account<'info, TokenAccount>,
+    pub admin: Signer<'info>,
     
-    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct CreateEpoch<'info> {
-    #[account(
-        mut,
-        seeds = [b"config"],
-        bump = config.bump,
-        has_one = oracle
-    )]
+#[instruction(epoch_number: u64)]
+pub struct MigrateEpoch<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump, has_one = admin)]
     pub config: Account<'info, Config>,
     
-    #[account(
-        init,
-        payer = oracle,
-        space = 8 + 8 + 8 + 8 + 8 + 8 + 2 + 1 + 1,
-        seeds = [b"epoch", (config.current_epoch + 1).to_le_bytes().as_ref()],
-        bump
-    )]
-    pub epoch: Account<'info, RewardEpoch>,
+    /// CHECK: v1 epochs are too short to deserialize as `RewardEpoch`; the address is checked by seeds
+    #[account(mut, seeds = [b"epoch", epoch_number.to_le_bytes().as_ref()], bump)]
+    pub epoch: UncheckedAccount<'info>,
     
     #[account(mut)]
-    pub oracle: Signer<'info>,
+    pub admin: Signer<'info>,
     
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(wallet: Pubkey, github_username: String, total_xp: u64, role_xp_data: Vec<XpCategory>, domain_xp_data: Vec<XpCategory>, skill_xp_data: Vec<XpCategory>)]
-pub struct SyncContributorXp<'info> {
-    #[account(seeds = [b"config"], bump = config.bump, has_one = oracle)]
+#[instruction(epoch_number: u64, wallet: Pubkey)]
+pub struct MigrateSnapshot<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin)]
     pub config: Account<'info, Config>,
     
+    /// CHECK: v1 snapshots are too short to deserialize as `EpochSnapshot`; the address is checked by seeds
     #[account(
         mut,
-        seeds = [b"epoch", config.current_epoch.to_le_bytes().as_ref()],
-        bump = epoch.bump
-    )]
-    pub epoch: Account<'info, RewardEpoch>,
-    
-    #[account(
-        init_if_needed,
-        payer = oracle,
-        space = 8 + 32 + 4 + MAX_GITHUB_USERNAME_LEN + 8 + 8 + 8 + 8 + 8 + 8 + 1,
-        seeds = [b"contributor", wallet.as_ref()],
-        bump
-    )]
-    pub contributor: Account<'info, Contributor>,
-    
-    #[account(
-        init_if_needed,
-        payer = oracle,
-        space = 8 + 32 + 8 + 8 + 1 + 8 + 1,
-        seeds = [b"snapshot", config.current_epoch.to_le_bytes().as_ref(), wallet.as_ref()],
+        seeds = [b"snapshot", epoch_number.to_le_bytes().as_ref(), wallet.as_ref()],
         bump
     )]
-    pub snapshot: Account<'info, EpochSnapshot>,
+    pub snapshot: UncheckedAccount<'info>,
     
     #[account(mut)]
-    pub oracle: Signer<'info>,
+    pub admin: Signer<'info>,
     
     pub system_program: Program<'info, System>,
 }
 
-#[derive(Accounts)]
-#[instruction(epoch_number: u64)]
-pub struct FinalizeEpoch<'info> {
-    #[account(seeds = [b"config"], bump = config.bump, has_one = oracle)]
-    pub config: Account<'info, Config>,
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey,
+    pub oracle: Pubkey,
+    pub usdc_mint: Pubkey,
+    pub sbt_mint: Pubkey,
+    pub usdc_vault: Pubkey,
+    pub current_epoch: u64,
+    pub total_epochs: u64,
+    pub xp_to_sbt_ratio: u64,
+    pub sbt_total_supply: u64,
+    pub sbt_minted: u64,
+    pub bump: u8,
+    // Everything below was appended after the v1 layout, which ends at `bump`
+    pub version: u8,
+    pub xp_weighting: XpWeighting,
+    pub treasury: Pubkey,
+    pub usdc_committed: u64,
+    pub pending_admin: Pubkey,
+    pub paused_flags: u8,
+    pub epoch_duration_secs: i64,
+    pub last_epoch_end: i64,
+    pub max_xp_increase: u64,
+    pub claim_window_secs: i64,
+    pub retroactive_window_secs: i64,
+    pub oracles: [Pubkey; MAX_ORACLES],
+    pub oracle_count: u8,
+    pub oracle_threshold: u8,
+    // Per-epoch caps on synced XP deltas and SBT earned; 0 disables the cap
+    pub max_xp_per_epoch: u64,
+    pub max_sbt_per_epoch: u64,
+    pub min_sync_interval_secs: i64,
+    pub reserved: [u8; CONFIG_RESERVED_LEN],
+}
+
+impl Config {
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+    pub const V1_SPACE: usize = 209;
+
+    /// Sets the fields a grown v1 config reads back as zero to the values `initialize` would use.
+    pub fn upgrade_from_v1(&mut self) {
+        self.version = CONFIG_VERSION;
+        self.xp_weighting = XpWeighting::Cumulative;
+        self.treasury = Pubkey::default();
+        self.usdc_committed = 0;
+        self.pending_admin = Pubkey::default();
+        self.paused_flags = 0;
+        self.epoch_duration_secs = SECONDS_PER_WEEK;
+        self.last_epoch_end = 0;
+        self.max_xp_increase = MAX_XP_INCREASE;
+        self.claim_window_secs = CLAIM_WINDOW_DAYS;
+        self.retroactive_window_secs = RETROACTIVE_WINDOW_SECS;
+        self.oracles = [Pubkey::default(); MAX_ORACLES];
+        self.oracle_count = 0;
+        self.oracle_threshold = 0;
+        self.max_xp_per_epoch = 0;
+        self.max_sbt_per_epoch = 0;
+        self.min_sync_interval_secs = 0;
+        self.reserved = [0u8; CONFIG_RESERVED_LEN];
+    }
+
+    pub fn is_oracle_set_member(&self, key: &Pubkey) -> bool {
+        self.oracles[..self.oracle_count as usize].contains(key)
+    }
+
+    pub fn require_not_paused(&self, flag: u8) -> Result<()> {
+        require!(self.paused_flags & flag == 0, ErrorCode::ProgramPaused);
+        Ok(())
+    }
+
+    pub fn release_commitment(&mut self, amount: u64) -> Result<()> {
+        self.usdc_committed = self
+            .usdc_committed
+            .checked_sub(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct RewardEpoch {
+    pub epoch_number: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub total_xp: u64,
+    pub usdc_reward_amount: u64,
+    pub contributor_count: u16,
+    pub finalized: bool,
+    pub bump: u8,
+    // Everything below was appended after the v1 layout, which ends at `bump`
+    pub version: u8,
+    pub merkle_root: [u8; 32],
+    pub merkle_leaf_count: u32,
+    pub unregistered_xp_root: [u8; 32],
+    pub unregistered_total_xp: u64,
+    pub xp_weighting: XpWeighting,
+    pub usdc_claimed_total: u64,
+    pub usdc_swept: u64,
+    pub swept: bool,
+    pub snapshot_count: u32,
+    pub xp_issued: u64,
+    pub sbt_issued: u64,
+    // Copied from Config at creation so later parameter changes cannot shorten an open epoch's claims
+    pub claim_window_secs: i64,
+    pub retroactive_window_secs: i64,
+    pub reserved: [u8; EPOCH_RESERVED_LEN],
+    // Sized per epoch by `space`, so only the length prefix counts toward INIT_SPACE. It stays
+    // last so the fixed fields keep their offsets when finalize_epoch grows the account.
+    #[max_len(0)]
+    pub claim_bitmap: Vec<u8>,
+}
+
+impl RewardEpoch {
+    pub const V1_SPACE: usize = 52;
+
+    pub const fn space(merkle_leaf_count: u32) -> usize {
+        8 + Self::INIT_SPACE + claim_bitmap_len(merkle_leaf_count)
+    }
+
+    /// Sets the fields a grown v1 epoch reads back as zero. v1 epochs weighted cumulative XP,
+    /// never used Merkle distributions or issuance caps, and had the fixed v1 claim windows.
+    pub fn upgrade_from_v1(&mut self, usdc_claimed_total: u64, snapshot_count: u32) -> Result<()> {
+        require!(
+            usdc_claimed_total <= self.usdc_reward_amount,
+            ErrorCode::EpochRewardExceeded
+        );
+        
+        self.version = EPOCH_VERSION;
+        self.merkle_root = [0u8; 32];
+        self.merkle_leaf_count = 0;
+        self.unregistered_xp_root = [0u8; 32];
+        self.unregistered_total_xp = 0;
+        self.xp_weighting = XpWeighting::Cumulative;
+        self.usdc_claimed_total = usdc_claimed_total;
+        self.usdc_swept = 0;
+        self.swept = false;
+        self.snapshot_count = snapshot_count;
+        self.xp_issued = 0;
+        self.sbt_issued = 0;
+        self.claim_window_secs = CLAIM_WINDOW_DAYS;
+        self.retroactive_window_secs = RETROACTIVE_WINDOW_SECS;
+        self.reserved = [0u8; EPOCH_RESERVED_LEN];
+        self.claim_bitmap = Vec::new();
+        Ok(())
+    }
+
+    pub fn reward_share(&self, xp: u64) -> Result<u64> {
+        let epoch_xp = self
+            .total_xp
+            .checked_add(self.unregistered_total_xp)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(epoch_xp > 0, ErrorCode::NoXpInEpoch);
+        
+        Ok((xp as u128)
+            .checked_mul(self.usdc_reward_amount as u128)
+            .ok_or(ErrorCode::MathOverflow)?
+            .checked_div(epoch_xp as u128)
+            .ok_or(ErrorCode::MathOverflow)? as u64)
+    }
+
+    pub fn claim_deadline(&self) -> i64 {
+        if self.unregistered_total_xp > 0 {
+            self.end_time + self.retroactive_window_secs
+        } else {
+            self.end_time + self.claim_window_secs
+        }
+    }
+
+    /// Ends claims on an epoch whose claim window has passed and returns the unclaimed remainder.
+    pub fn sweep(&mut self, now: i64) -> Result<u64> {
+        require!(self.finalized, ErrorCode::EpochNotFinalized);
+        require!(!self.swept, ErrorCode::EpochAlreadySwept);
+        require!(now > self.claim_deadline(), ErrorCode::ClaimWindowNotExpired);
+        
+        let remainder = self
+            .usdc_reward_amount
+            .checked_sub(self.usdc_claimed_total)
+            .ok_or(ErrorCode::MathOverflow)?;
+        self.usdc_swept = remainder;
+        self.swept = true;
+        Ok(remainder)
+    }
+
+    /// An epoch can only be archived once nothing is left to pay out or reclaim from it.
+    pub fn require_closable(&self, now: i64) -> Result<()> {
+        require!(self.finalized, ErrorCode::EpochNotFinalized);
+        require!(now > self.claim_deadline(), ErrorCode::ClaimWindowNotExpired);
+        require!(self.swept, ErrorCode::EpochNotSwept);
+        require!(self.snapshot_count == 0, ErrorCode::SnapshotsStillOpen);
+        Ok(())
+    }
+
+    pub fn add_rollover(&mut self, amount: u64) -> Result<()> {
+        require!(!self.finalized, ErrorCode::EpochFinalized);
+        
+        self.usdc_reward_amount = self
+            .usdc_reward_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        Ok(())
+    }
+
+    pub fn record_claim(&mut self, amount: u64) -> Result<()> {
+        require!(!self.swept, ErrorCode::EpochAlreadySwept);
+        
+        let claimed_total = self
+            .usdc_claimed_total
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+        require!(claimed_total <= self.usdc_reward_amount, ErrorCode::EpochRewardExceeded);
+        
+        self.usdc_claimed_total = claimed_total;
+        Ok(())
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Contributor {
+    pub wallet: Pubkey,
+    #[max_len(MAX_GITHUB_USERNAME_LEN)]
+    pub github_username: String,
+    pub total_xp: u64,
+    pub total_sbt_claimable: u64,
+    pub total_sbt_claimed: u64,
+    pub lifetime_usdc_earned: u64,
+    pub last_claim_epoch: u64,
+    pub registered_at: i64,
+    pub bump: u8,
+    pub version: u8,
+    pub last_attestation_nonce: u64,
+    pub last_synced_at: i64,
+    // USDC claims pay this address's ATA instead of the wallet's; a change is staged in
+    // pending_payout_address and replaces it once payout_effective_at passes (0 when none is staged)
+    pub payout_address: Pubkey,
+    pub payout_effective_at: i64,
+    pub pending_payout_address: Pubkey,
+    pub reserved: [u8; CONTRIBUTOR_RESERVED_LEN],
+}
+
+impl Contributor {
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+    pub const V1_SPACE: usize = 132;
     
-    #[account(
-        mut,
-        seeds = [b"epoch", epoch_number.to_le_bytes().as_ref()],
-        bump = epoch.bump
-    )]
-    pub epoch: Account<'info, RewardEpoch>,
+    /// Resets the fields appended after the v1 layout. The username is variable length, so the
+    /// tail of a v1 account can hold stale bytes from a longer username rather than zeroes.
+    pub fn upgrade_from_v1(&mut self) {
+        self.version = CONTRIBUTOR_VERSION;
+        self.last_attestation_nonce = 0;
+        self.last_synced_at = 0;
+        self.payout_address = Pubkey::default();
+        self.payout_effective_at = 0;
+        self.pending_payout_address = Pubkey::default();
+        self.reserved = [0u8; CONTRIBUTOR_RESERVED_LEN];
+    }
+    
+    pub fn payout_owner(&self, now: i64) -> Pubkey {
+        let payout_address = if self.payout_change_due(now) {
+            self.pending_payout_address
+        } else {
+            self.payout_address
+        };
+        if payout_address == Pubkey::default() {
+            self.wallet
+        } else {
+            payout_address
+        }
+    }
+    
+    pub fn settle_payout_address(&mut self, now: i64) {
+        if self.payout_change_due(now) {
+            self.payout_address = self.pending_payout_address;
+            self.pending_payout_address = Pubkey::default();
+            self.payout_effective_at = 0;
+        }
+    }
+    
+    fn payout_change_due(&self, now: i64) -> bool {
+        self.payout_effective_at != 0 && now >= self.payout_effective_at
+    }
+}
+
+#[account]
+#[derive(Default, InitSpace)]
+pub struct EpochSnapshot {
+    pub contributor: Pubkey,
+    pub epoch: u64,
+    pub xp: u64,
+    pub usdc_claimed: bool,
+    pub sbt_earned: u64,
+    pub bump: u8,
+    // Everything below was appended after the v1 layout, which ends at `bump`
+    pub version: u8,
+    pub baseline_xp: u64,
+    pub funder: Pubkey,
+    pub reserved: [u8; SNAPSHOT_RESERVED_LEN],
+}
+
+impl EpochSnapshot {
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+    pub const V1_SPACE: usize = 66;
+
+    /// v1 epochs weighted cumulative XP, so the baseline stays zero; the oracle paid for v1 snapshots.
+    pub fn upgrade_from_v1(&mut self, funder: Pubkey) {
+        self.version = SNAPSHOT_VERSION;
+        self.baseline_xp = 0;
+        self.funder = funder;
+        self.reserved = [0u8; SNAPSHOT_RESERVED_LEN];
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct XpProposal {
+    pub epoch: u64,
+    pub wallet: Pubkey,
+    pub total_xp: u64,
+    pub proposer: Pubkey,
+    pub approvers: [Pubkey; MAX_ORACLES],
+    pub approval_count: u8,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl XpProposal {
+    pub const SPACE: usize = 8 + Self::INIT_SPACE;
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug, InitSpace)]
+pub enum XpWeighting {
+    Cumulative,
+    PerEpoch,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum SweepDestination {
+    Rollover,
+    Treasury,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct XpSyncEntry {
+    pub wallet: Pubkey,
+    pub total_xp: u64,
+    pub github_username: String,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct ProtocolParams {
+    pub max_xp_increase: u64,
+    pub claim_window_secs: i64,
+    pub retroactive_window_secs: i64,
+    pub xp_to_sbt_ratio: u64,
+    pub max_xp_per_epoch: u64,
+    pub max_sbt_per_epoch: u64,
+    pub min_sync_interval_secs: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct MerkleDistribution {
+    pub root: [u8; 32],
+    pub leaf_count: u32,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct XpCategory {
+    pub name: String,
+    pub amount: u64,
+}
+
+fn apply_xp_sync(
+    config: &Config,
+    epoch: &mut RewardEpoch,
+    contributor: &mut Contributor,
+    snapshot: &mut EpochSnapshot,
+    wallet: Pubkey,
+    total_xp: u64,
+    snapshot_bump: u8,
+) -> Result<u64> {
+    let old_xp = contributor.total_xp;
+    let xp_delta = total_xp.saturating_sub(old_xp);
+    
+    require!(
+        xp_delta <= config.max_xp_increase,
+        ErrorCode::XpTooHigh
+    );
+    
+    let now = Clock::get()?.unix_timestamp;
+    require!(
+        now - contributor.last_synced_at >= config.min_sync_interval_secs,
+        ErrorCode::SyncTooFrequent
+    );
+    
+    let is_new_snapshot = snapshot.contributor == Pubkey::default();
+    let snapshot_old_xp = snapshot.xp;
+    
+    if is_new_snapshot {
+        snapshot.baseline_xp = old_xp;
+    }
+    
+    let epoch_xp = match epoch.xp_weighting {
+        XpWeighting::Cumulative => total_xp,
+        XpWeighting::PerEpoch => total_xp.saturating_sub(snapshot.baseline_xp),
+    };
+    
+    let sbt_earned = xp_delta.checked_mul(config.xp_to_sbt_ratio).ok_or(ErrorCode::MathOverflow)?;
+    
+    epoch.xp_issued = epoch.xp_issued.checked_add(xp_delta).ok_or(ErrorCode::MathOverflow)?;
+    epoch.sbt_issued = epoch.sbt_issued.checked_add(sbt_earned).ok_or(ErrorCode::MathOverflow)?;
+    require!(
+        config.max_xp_per_epoch == 0 || epoch.xp_issued <= config.max_xp_per_epoch,
+        ErrorCode::EpochXpCapExceeded
+    );
+    require!(
+        config.max_sbt_per_epoch == 0 || epoch.sbt_issued <= config.max_sbt_per_epoch,
+        ErrorCode::EpochSbtCapExceeded
+    );
     
-    pub oracle: Signer<'info>,
+    contributor.total_xp = total_xp;
+    contributor.last_synced_at = now;
+    contributor.total_sbt_claimable = contributor
+        .total_sbt_claimable
+        .checked_add(sbt_earned)
+        .ok_or(ErrorCode::MathOverflow)?;
+    
+    snapshot.contributor = wallet;
+    snapshot.epoch = epoch.epoch_number;
+    snapshot.xp = epoch_xp;
+    snapshot.usdc_claimed = false;
+    snapshot.sbt_earned = sbt_earned;
+    snapshot.bump = snapshot_bump;
+    snapshot.version = SNAPSHOT_VERSION;
+    
+    if is_new_snapshot {
+        epoch.contributor_count += 1;
+        epoch.total_xp = epoch.total_xp
+            .checked_add(epoch_xp)
+            .ok_or(ErrorCode::MathOverflow)?;
+    } else {
+        epoch.total_xp = epoch.total_xp
+            .checked_sub(snapshot_old_xp)
+            .ok_or(ErrorCode::MathOverflow)?
+            .checked_add(epoch_xp)
+            .ok_or(ErrorCode::MathOverflow)?;
+    }
+    
+    msg!("Synced XP {} for {} in epoch {} (epoch weight {}, +{} SBT)", 
+        total_xp, wallet, epoch.epoch_number, epoch_xp, sbt_earned);
+    Ok(sbt_earned)
 }
 
-#[derive(Accounts)]
-pub struct RegisterContributor<'info> {
-    #[account(
-        init,
-        payer = wallet,
-        space = 8 + 32 + 4 + MAX_GITHUB_USERNAME_LEN + 8 + 8 + 8 + 8 + 8 + 8 + 1,
-        seeds = [b"contributor", wallet.key().as_ref()],
-        bump
-    )]
-    pub contributor: Account<'info, Contributor>,
+/// Pays `amount` out of the reward vault and returns what the recipient actually receives,
+/// which is less than `amount` when the reward mint charges a Token-2022 transfer fee.
+fn transfer_from_vault<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    usdc_vault: &InterfaceAccount<'info, TokenAccount>,
+    usdc_mint: &InterfaceAccount<'info, Mint>,
+    to: AccountInfo<'info>,
+    config: &Account<'info, Config>,
+    amount: u64,
+) -> Result<u64> {
+    let seeds = &[b"config".as_ref(), &[config.bump]];
+    let signer = &[&seeds[..]];
     
-    #[account(mut)]
-    pub wallet: Signer<'info>,
+    transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: usdc_vault.to_account_info(),
+                mint: usdc_mint.to_account_info(),
+                to,
+                authority: config.to_account_info(),
+            },
+            signer,
+        ),
+        amount,
+        usdc_mint.decimals,
+    )?;
     
-    pub system_program: Program<'info, System>,
+    let fee = transfer_fee(usdc_mint, amount)?;
+    amount.checked_sub(fee).ok_or(error!(ErrorCode::MathOverflow))
 }
 
-#[derive(Accounts)]
-pub struct UpdateGithubLink<'info> {
-    #[account(
-        mut,
-        seeds = [b"contributor", wallet.key().as_ref()],
-        bump = contributor.bump,
-        has_one = wallet
-    )]
-    pub contributor: Account<'info, Contributor>,
+fn transfer_fee(mint: &InterfaceAccount<Mint>, amount: u64) -> Result<u64> {
+    let mint_info = mint.to_account_info();
+    if *mint_info.owner != spl_token_2022::ID {
+        return Ok(0);
+    }
     
-    pub wallet: Signer<'info>,
+    match get_mint_extension_data::<TransferFeeConfig>(&mint_info) {
+        Ok(fee_config) => fee_config
+            .calculate_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(error!(ErrorCode::MathOverflow)),
+        Err(_) => Ok(0),
+    }
 }
 
-#[derive(Accounts)]
-#[instruction(epoch_number: u64)]
-pub struct ClaimUsdcRewards<'info> {
-    #[account(seeds = [b"config"], bump = config.bump)]
-    pub config: Account<'info, Config>,
-    
-    #[account(
-        seeds = [b"epoch", epoch_number.to_le_bytes().as_ref()],
-        bump = epoch.bump
-    )]
-    pub epoch: Account<'info, RewardEpoch>,
+fn validate_sbt_mint(mint_info: &AccountInfo, config: &Pubkey) -> Result<()> {
+    require_keys_eq!(*mint_info.owner, spl_token_2022::ID, ErrorCode::InvalidSbtMint);
     
-    #[account(
-        mut,
-        seeds = [b"snapshot", epoch_number.to_le_bytes().as_ref(), wallet.key().as_ref()],
-        bump = snapshot.bump,
-        has_one = contributor
-    )]
-    pub snapshot: Account<'info, EpochSnapshot>,
+    let data = mint_info.try_borrow_data()?;
+    let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)
+        .map_err(|_| error!(ErrorCode::InvalidSbtMint))?;
+    let extensions = mint
+        .get_extension_types()
+        .map_err(|_| error!(ErrorCode::InvalidSbtMint))?;
     
-    #[account(
-        mut,
-        seeds = [b"contributor", wallet.key().as_ref()],
-        bump = contributor.bump,
-        has_one = wallet
-    )]
-    pub contributor: Account<'info, Contributor>,
+    require!(
+        extensions.contains(&ExtensionType::NonTransferable)
+            && extensions.contains(&ExtensionType::MetadataPointer),
+        ErrorCode::InvalidSbtMint
+    );
+    require!(mint.base.mint_authority == COption::Some(*config), ErrorCode::InvalidSbtMint);
+    Ok(())
+}
+
+fn xp_attestation_message(wallet: &Pubkey, epoch: u64, total_xp: u64, nonce: u64) -> Vec<u8> {
+    [
+        XP_ATTESTATION_DOMAIN,
+        crate::ID.as_ref(),
+        wallet.as_ref(),
+        &epoch.to_le_bytes(),
+        &total_xp.to_le_bytes(),
+        &nonce.to_le_bytes(),
+    ]
+    .concat()
+}
+
+fn verify_oracle_attestation(instructions: &AccountInfo, oracle: &Pubkey, message: &[u8]) -> Result<()> {
+    let current_index = load_current_index_checked(instructions)?;
+    require!(current_index > 0, ErrorCode::InvalidAttestation);
     
-    #[account(
-        mut,
-        constraint = usdc_vault.key() == config.usdc_vault
-    )]
-    pub usdc_vault: Account<'info, TokenAccount>,
+    let ix = load_instruction_at_checked((current_index - 1) as usize, instructions)?;
+    require_keys_eq!(ix.program_id, ed25519_program::ID, ErrorCode::InvalidAttestation);
+    require!(
+        ix.accounts.is_empty() && ix.data.len() >= ED25519_OFFSETS_START + ED25519_OFFSETS_LEN && ix.data[0] == 1,
+        ErrorCode::InvalidAttestation
+    );
     
-    #[account(mut)]
-    pub contributor_token_account: Account<'info, TokenAccount>,
+    let offsets = &ix.data[ED25519_OFFSETS_START..ED25519_OFFSETS_START + ED25519_OFFSETS_LEN];
+    let read_u16 = |i: usize| u16::from_le_bytes([offsets[i], offsets[i + 1]]);
+    let public_key_offset = read_u16(4) as usize;
+    let message_offset = read_u16(8) as usize;
+    let message_size = read_u16(10) as usize;
     
-    pub wallet: Signer<'info>,
+    // Offsets must point into the Ed25519 instruction itself, not some other instruction's data
+    require!(
+        read_u16(2) == u16::MAX && read_u16(6) == u16::MAX && read_u16(12) == u16::MAX,
+        ErrorCode::InvalidAttestation
+    );
     
-    pub token_program: Program<'info, Token>,
+    let signer = ix
+        .data
+        .get(public_key_offset..public_key_offset + 32)
+        .ok_or(ErrorCode::InvalidAttestation)?;
+    let signed_message = ix
+        .data
+        .get(message_offset..message_offset + message_size)
+        .ok_or(ErrorCode::InvalidAttestation)?;
+    require!(signer == oracle.as_ref(), ErrorCode::InvalidAttestation);
+    require!(signed_message == message, ErrorCode::InvalidAttestation);
+    Ok(())
 }
 
-#[derive(Accounts)]
-pub struct ClaimSbtTokens<'info> {
-    #[account(
-        mut,
-        seeds = [b"config"],
-        bump = config.bump
-    )]
-    pub config: Account<'info, Config>,
+fn load_batch_contributor(info: &AccountInfo, wallet: &Pubkey) -> Result<Option<Contributor>> {
+    if info.owner == &crate::ID && !info.data_is_empty() {
+        let contributor = Contributor::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        if contributor.wallet == *wallet {
+            let expected = Pubkey::create_program_address(
+                &[b"contributor", wallet.as_ref(), &[contributor.bump]],
+                &crate::ID,
+            )
+            .map_err(|_| error!(ErrorCode::InvalidBatchAccount))?;
+            require_keys_eq!(info.key(), expected, ErrorCode::InvalidBatchAccount);
+            return Ok(Some(contributor));
+        }
+    }
     
-    #[account(
-        mut,
-        seeds = [b"contributor", wallet.key().as_ref()],
-        bump = contributor.bump,
-        has_one = wallet
-    )]
-    pub contributor: Account<'info, Contributor>,
+    let (expected, _) = Pubkey::find_program_address(&[b"contributor", wallet.as_ref()], &crate::ID);
+    require_keys_eq!(info.key(), expected, ErrorCode::InvalidBatchAccount);
+    Ok(None)
+}
+
+fn load_or_create_batch_snapshot<'info>(
+    info: &AccountInfo<'info>,
+    oracle: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    epoch_number: u64,
+    wallet: &Pubkey,
+) -> Result<(EpochSnapshot, u8)> {
+    let epoch_seed = epoch_number.to_le_bytes();
+    let (expected, bump) = Pubkey::find_program_address(
+        &[b"snapshot", epoch_seed.as_ref(), wallet.as_ref()],
+        &crate::ID,
+    );
+    require_keys_eq!(info.key(), expected, ErrorCode::InvalidBatchAccount);
     
-    #[account(
-        mut,
-        constraint = sbt_mint.key() == config.sbt_mint
-    )]
-    pub sbt_mint: Account<'info, Mint>,
+    if info.owner == &crate::ID && !info.data_is_empty() {
+        let snapshot = EpochSnapshot::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+        return Ok((snapshot, bump));
+    }
     
-    #[account(mut)]
-    pub contributor_sbt_account: Account<'info, TokenAccount>,
+    create_pda_account(
+        info,
+        oracle,
+        system_program,
+        EpochSnapshot::SPACE,
+        &crate::ID,
+        &[b"snapshot", epoch_seed.as_ref(), wallet.as_ref(), &[bump]],
+    )?;
     
-    pub wallet: Signer<'info>,
+    Ok((
+        EpochSnapshot {
+            funder: oracle.key(),
+            ..Default::default()
+        },
+        bump,
+    ))
+}
+
+/// Creates a PDA the way Anchor's `init` does, so lamports sent to the
+/// address ahead of time cannot block `create_account`.
+fn create_pda_account<'info>(
+    info: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    owner: &Pubkey,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    if info.lamports() == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                system_program::CreateAccount {
+                    from: payer.clone(),
+                    to: info.clone(),
+                },
+                &[seeds],
+            ),
+            Rent::get()?.minimum_balance(space),
+            space as u64,
+            owner,
+        );
+    }
     
-    pub token_2022_program: Program<'info, Token2022>,
+    fund_rent(info, payer, system_program, space)?;
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Allocate {
+                account_to_allocate: info.clone(),
+            },
+            &[seeds],
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Assign {
+                account_to_assign: info.clone(),
+            },
+            &[seeds],
+        ),
+        owner,
+    )
 }
 
-#[account]
-pub struct Config {
-    pub admin: Pubkey,
-    pub oracle: Pubkey,
-    pub usdc_mint: Pubkey,
-    pub sbt_mint: Pubkey,
-    pub usdc_vault: Pubkey,
-    pub current_epoch: u64,
-    pub total_epochs: u64,
-    pub xp_to_sbt_ratio: u64,
-    pub sbt_total_supply: u64,
-    pub sbt_minted: u64,
-    pub bump: u8,
+fn grow_account<'info>(
+    info: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    new_len: usize,
+) -> Result<()> {
+    fund_rent(info, payer, system_program, new_len)?;
+    info.resize(new_len)?;
+    Ok(())
 }
 
-#[account]
-pub struct RewardEpoch {
-    pub epoch_number: u64,
-    pub start_time: i64,
-    pub end_time: i64,
-    pub total_xp: u64,
-    pub usdc_reward_amount: u64,
-    pub contributor_count: u16,
-    pub finalized: bool,
-    pub bump: u8,
+/// Tops `info` up to the rent-exempt minimum for `len` bytes.
+fn fund_rent<'info>(
+    info: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    len: usize,
+) -> Result<()> {
+    let shortfall = Rent::get()?
+        .minimum_balance(len)
+        .max(1)
+        .saturating_sub(info.lamports());
+    if shortfall > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: info.clone(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    Ok(())
 }
 
-#[account]
-pub struct Contributor {
-    pub wallet: Pubkey,
-    pub github_username: String,
-    pub total_xp: u64,
-    pub total_sbt_claimable: u64,
-    pub total_sbt_claimed: u64,
-    pub lifetime_usdc_earned: u64,
-    pub last_claim_epoch: u64,
-    pub registered_at: i64,
-    pub bump: u8,
+fn snapshot_claim_amount(
+    epoch: &RewardEpoch,
+    snapshot: &EpochSnapshot,
+    contributor: &Contributor,
+    now: i64,
+) -> Result<u64> {
+    require!(epoch.finalized, ErrorCode::EpochNotFinalized);
+    // Merkle epochs pay only through claim_usdc_with_proof, whose bitmap cannot see snapshot claims
+    require!(epoch.merkle_leaf_count == 0, ErrorCode::MerkleDistributionActive);
+    require!(!snapshot.usdc_claimed, ErrorCode::AlreadyClaimed);
+    require!(
+        now <= epoch.end_time + epoch.claim_window_secs,
+        ErrorCode::ClaimWindowExpired
+    );
+    require!(epoch.total_xp > 0, ErrorCode::NoXpInEpoch);
+    require!(
+        epoch.end_time >= contributor.registered_at - epoch.retroactive_window_secs,
+        ErrorCode::EpochTooOld
+    );
+    
+    epoch.reward_share(snapshot.xp)
 }
 
-#[account]
-pub struct EpochSnapshot {
-    pub contributor: Pubkey,
-    pub epoch: u64,
-    pub xp: u64,
-    pub usdc_claimed: bool,
-    pub sbt_earned: u64,
-    pub bump: u8,
+const fn claim_bitmap_len(leaf_count: u32) -> usize {
+    (leaf_count as usize).div_ceil(8)
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
-pub struct XpCategory {
-    pub name: String,
-    pub amount: u64,
+fn merkle_leaf(index: u32, wallet: &Pubkey, xp: u64, amount: u64) -> [u8; 32] {
+    hashv(&[
+        &[MERKLE_LEAF_PREFIX],
+        &index.to_le_bytes(),
+        wallet.as_ref(),
+        &xp.to_le_bytes(),
+        &amount.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+fn unregistered_xp_leaf(wallet: &Pubkey, xp: u64) -> [u8; 32] {
+    hashv(&[&[UNREGISTERED_LEAF_PREFIX], wallet.as_ref(), &xp.to_le_bytes()]).to_bytes()
+}
+
+fn verify_merkle_proof(proof: &[[u8; 32]], root: [u8; 32], leaf: [u8; 32]) -> bool {
+    let mut computed = leaf;
+    for node in proof {
+        computed = if computed <= *node {
+            hashv(&[&[MERKLE_NODE_PREFIX], &computed, node]).to_bytes()
+        } else {
+            hashv(&[&[MERKLE_NODE_PREFIX], node, &computed]).to_bytes()
+        };
+    }
+    computed == root
 }
 
 #[event]
@@ -681,6 +3261,51 @@ pub struct XpSyncedEvent {
     pub is_registered: bool,
 }
 
+#[event]
+pub struct EpochArchivedEvent {
+    pub epoch: u64,
+    pub total_xp: u64,
+    pub unregistered_total_xp: u64,
+    pub contributor_count: u16,
+    pub usdc_reward_amount: u64,
+    pub usdc_distributed: u64,
+    pub usdc_swept: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct XpCorrectedEvent {
+    pub wallet: Pubkey,
+    pub epoch: u64,
+    pub previous_xp: u64,
+    pub corrected_xp: u64,
+    pub sbt_clawed_back: u64,
+    pub reason_code: u8,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PayoutAddressChangedEvent {
+    pub wallet: Pubkey,
+    pub payout_address: Pubkey,
+    pub effective_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ParamsUpdatedEvent {
+    pub max_xp_increase: u64,
+    pub claim_window_secs: i64,
+    pub retroactive_window_secs: i64,
+    pub xp_to_sbt_ratio: u64,
+    pub max_xp_per_epoch: u64,
+    pub max_sbt_per_epoch: u64,
+    pub min_sync_interval_secs: i64,
+    pub admin: Pubkey,
+    pub timestamp: i64,
+}
+
 #[error_code]
 pub enum ErrorCode {
     #[msg("XP increase too high")]
@@ -697,7 +3322,7 @@ pub enum ErrorCode {
     EpochNotFinalized,
     #[msg("Rewards already claimed for this epoch")]
     AlreadyClaimed,
-    #[msg("Claim window has expired (30 days after epoch end)")]
+    #[msg("Claim window for this epoch has expired")]
     ClaimWindowExpired,
     #[msg("No XP recorded in this epoch")]
     NoXpInEpoch,
@@ -713,6 +3338,487 @@ pub enum ErrorCode {
     SbtSupplyExhausted,
     #[msg("Contributor not registered. Register first to claim rewards.")]
     ContributorNotRegistered,
-    #[msg("Epoch ended before your 90-day retroactive claim window.")]
+    #[msg("Epoch ended before your retroactive claim window.")]
     EpochTooOld,
+    #[msg("Invalid Merkle distribution")]
+    InvalidMerkleDistribution,
+    #[msg("Epoch has no Merkle distribution")]
+    MerkleDistributionNotSet,
+    #[msg("Invalid Merkle proof")]
+    InvalidMerkleProof,
+    #[msg("No unregistered XP was committed for this epoch")]
+    NoUnregisteredXpCommitted,
+    #[msg("Contributor is already registered")]
+    AlreadyRegistered,
+    #[msg("Batch size is empty or exceeds the maximum")]
+    InvalidBatchSize,
+    #[msg("Batch account does not match the expected PDA")]
+    InvalidBatchAccount,
+    #[msg("Epoch has already been swept")]
+    EpochAlreadySwept,
+    #[msg("Claims would exceed the epoch reward amount")]
+    EpochRewardExceeded,
+    #[msg("Claim window has not expired yet")]
+    ClaimWindowNotExpired,
+    #[msg("Sweep destination account missing or invalid")]
+    MissingSweepDestination,
+    #[msg("Treasury address is not configured")]
+    TreasuryNotSet,
+    #[msg("Signer is not authorized for this instruction")]
+    Unauthorized,
+    #[msg("USDC vault balance does not cover outstanding reward commitments")]
+    InsufficientVaultBalance,
+    #[msg("The current epoch cannot be closed")]
+    EpochStillActive,
+    #[msg("Epoch must be swept before it can be closed")]
+    EpochNotSwept,
+    #[msg("Epoch still has open snapshot accounts")]
+    SnapshotsStillOpen,
+    #[msg("Signer is not the pending admin")]
+    NotPendingAdmin,
+    #[msg("This instruction is paused")]
+    ProgramPaused,
+    #[msg("Unknown pause flag bits")]
+    InvalidPauseFlags,
+    #[msg("Epoch duration is out of bounds")]
+    InvalidEpochDuration,
+    #[msg("Epoch would overlap the previous epoch")]
+    EpochOverlap,
+    #[msg("Epoch start time is too far in the future or already elapsed")]
+    InvalidEpochStart,
+    #[msg("Epoch has not started yet")]
+    EpochNotStarted,
+    #[msg("Previous epoch account is required")]
+    MissingPreviousEpoch,
+    #[msg("Previous epoch is still running and not finalized")]
+    PreviousEpochNotFinalized,
+    #[msg("Protocol parameter is out of bounds")]
+    InvalidParams,
+    #[msg("Account is already on the current layout")]
+    AlreadyMigrated,
+    #[msg("Oracle set or threshold is invalid")]
+    InvalidOracleSet,
+    #[msg("Signer is not in the oracle set")]
+    NotOracleSetMember,
+    #[msg("Multi-oracle attestation is not enabled")]
+    MultiOracleDisabled,
+    #[msg("XP updates for registered contributors require threshold attestation")]
+    ThresholdAttestationRequired,
+    #[msg("Oracle already approved this XP update")]
+    AlreadyApproved,
+    #[msg("Missing or invalid oracle XP attestation")]
+    InvalidAttestation,
+    #[msg("Attestation nonce has already been used")]
+    StaleAttestationNonce,
+    #[msg("Contributor was synced too recently")]
+    SyncTooFrequent,
+    #[msg("Epoch XP issuance cap exceeded")]
+    EpochXpCapExceeded,
+    #[msg("Epoch SBT issuance cap exceeded")]
+    EpochSbtCapExceeded,
+    #[msg("Corrected XP must be lower than the current total")]
+    InvalidXpCorrection,
+    #[msg("SBT mint must be a Token-2022 NonTransferable mint with a metadata pointer, minted by the config")]
+    InvalidSbtMint,
+    #[msg("SBT mint is already set")]
+    SbtMintAlreadySet,
+    #[msg("USDC mint does not match the configured mint")]
+    InvalidUsdcMint,
+    #[msg("Claim destination must be owned by the contributor's payout address")]
+    InvalidPayoutAddress,
+    #[msg("Epoch pays out through its Merkle distribution; claim with a proof")]
+    MerkleDistributionActive,
+    #[msg("SBT metadata name, symbol or URI is too long")]
+    InvalidSbtMetadata,
+    #[msg("Attested XP is below the contributor's current total")]
+    AttestationBelowCurrentXp,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zeroed<T: AccountDeserialize>(len: usize) -> T {
+        T::try_deserialize_unchecked(&mut &vec![0u8; len][..]).unwrap()
+    }
+
+    fn serialized_len<T: AccountSerialize>(account: &T) -> usize {
+        let mut data = Vec::new();
+        account.try_serialize(&mut data).unwrap();
+        data.len()
+    }
+
+    // Field-for-field copies of the deployed v1 accounts
+    #[derive(AnchorSerialize)]
+    struct ConfigV1 {
+        admin: Pubkey,
+        oracle: Pubkey,
+        usdc_mint: Pubkey,
+        sbt_mint: Pubkey,
+        usdc_vault: Pubkey,
+        current_epoch: u64,
+        total_epochs: u64,
+        xp_to_sbt_ratio: u64,
+        sbt_total_supply: u64,
+        sbt_minted: u64,
+        bump: u8,
+    }
+
+    #[derive(AnchorSerialize)]
+    struct ContributorV1 {
+        wallet: Pubkey,
+        github_username: String,
+        total_xp: u64,
+        total_sbt_claimable: u64,
+        total_sbt_claimed: u64,
+        lifetime_usdc_earned: u64,
+        last_claim_epoch: u64,
+        registered_at: i64,
+        bump: u8,
+    }
+
+    #[derive(AnchorSerialize)]
+    struct RewardEpochV1 {
+        epoch_number: u64,
+        start_time: i64,
+        end_time: i64,
+        total_xp: u64,
+        usdc_reward_amount: u64,
+        contributor_count: u16,
+        finalized: bool,
+        bump: u8,
+    }
+
+    #[derive(AnchorSerialize)]
+    struct EpochSnapshotV1 {
+        contributor: Pubkey,
+        epoch: u64,
+        xp: u64,
+        usdc_claimed: bool,
+        sbt_earned: u64,
+        bump: u8,
+    }
+
+    /// Lays out a v1 account the way it sits on chain, `fill` standing in for unused bytes at the
+    /// end of the allocation, then zero-extends it to `new_len` as `grow_account` does.
+    fn grown_v1_account<T: AnchorSerialize>(
+        discriminator: &[u8],
+        account: &T,
+        v1_len: usize,
+        fill: u8,
+        new_len: usize,
+    ) -> Vec<u8> {
+        let mut data = discriminator.to_vec();
+        account.serialize(&mut data).unwrap();
+        assert!(data.len() <= v1_len);
+        data.resize(v1_len, fill);
+        data.resize(new_len, 0);
+        data
+    }
+
+    #[test]
+    fn v1_layouts_match_deployed_sizes() {
+        let config = ConfigV1 {
+            admin: Pubkey::default(),
+            oracle: Pubkey::default(),
+            usdc_mint: Pubkey::default(),
+            sbt_mint: Pubkey::default(),
+            usdc_vault: Pubkey::default(),
+            current_epoch: 0,
+            total_epochs: 0,
+            xp_to_sbt_ratio: 0,
+            sbt_total_supply: 0,
+            sbt_minted: 0,
+            bump: 0,
+        };
+        assert_eq!(8 + config.try_to_vec().unwrap().len(), Config::V1_SPACE);
+
+        let contributor = ContributorV1 {
+            wallet: Pubkey::default(),
+            github_username: "a".repeat(MAX_GITHUB_USERNAME_LEN),
+            total_xp: 0,
+            total_sbt_claimable: 0,
+            total_sbt_claimed: 0,
+            lifetime_usdc_earned: 0,
+            last_claim_epoch: 0,
+            registered_at: 0,
+            bump: 0,
+        };
+        assert_eq!(8 + contributor.try_to_vec().unwrap().len(), Contributor::V1_SPACE);
+
+        let snapshot = EpochSnapshotV1 {
+            contributor: Pubkey::default(),
+            epoch: 0,
+            xp: 0,
+            usdc_claimed: false,
+            sbt_earned: 0,
+            bump: 0,
+        };
+        assert_eq!(8 + snapshot.try_to_vec().unwrap().len(), EpochSnapshot::V1_SPACE);
+
+        let epoch = RewardEpochV1 {
+            epoch_number: 0,
+            start_time: 0,
+            end_time: 0,
+            total_xp: 0,
+            usdc_reward_amount: 0,
+            contributor_count: 0,
+            finalized: false,
+            bump: 0,
+        };
+        assert_eq!(8 + epoch.try_to_vec().unwrap().len(), RewardEpoch::V1_SPACE);
+    }
+
+    #[test]
+    fn account_sizes_match_v2_layouts() {
+        // Growing a v2 account must consume reserved padding or go through a migrate_* instruction
+        assert_eq!(Config::SPACE, 452);
+        assert_eq!(Contributor::SPACE, 229);
+        assert_eq!(EpochSnapshot::SPACE, 139);
+        assert_eq!(RewardEpoch::space(0), 203);
+    }
+
+    #[test]
+    fn max_size_accounts_fit_derived_space() {
+        let config: Config = zeroed(Config::SPACE);
+        assert_eq!(serialized_len(&config), Config::SPACE);
+
+        let mut contributor: Contributor = zeroed(Contributor::SPACE);
+        contributor.github_username = "a".repeat(MAX_GITHUB_USERNAME_LEN);
+        assert_eq!(serialized_len(&contributor), Contributor::SPACE);
+
+        assert_eq!(serialized_len(&EpochSnapshot::default()), EpochSnapshot::SPACE);
+
+        let mut epoch: RewardEpoch = zeroed(RewardEpoch::space(0));
+        assert_eq!(serialized_len(&epoch), RewardEpoch::space(0));
+        epoch.claim_bitmap = vec![0u8; claim_bitmap_len(MAX_MERKLE_LEAVES)];
+        assert_eq!(serialized_len(&epoch), RewardEpoch::space(MAX_MERKLE_LEAVES));
+
+        let proposal: XpProposal = zeroed(XpProposal::SPACE);
+        assert_eq!(serialized_len(&proposal), XpProposal::SPACE);
+    }
+
+    #[test]
+    fn migrates_v1_config() {
+        let admin = Pubkey::new_unique();
+        let oracle = Pubkey::new_unique();
+        let data = grown_v1_account(
+            Config::DISCRIMINATOR,
+            &ConfigV1 {
+                admin,
+                oracle,
+                usdc_mint: Pubkey::new_unique(),
+                sbt_mint: Pubkey::new_unique(),
+                usdc_vault: Pubkey::new_unique(),
+                current_epoch: 4,
+                total_epochs: 4,
+                xp_to_sbt_ratio: 50,
+                sbt_total_supply: SBT_MAX_SUPPLY,
+                sbt_minted: 1_234,
+                bump: 254,
+            },
+            Config::V1_SPACE,
+            0,
+            Config::SPACE,
+        );
+
+        let mut config = Config::try_deserialize(&mut &data[..]).unwrap();
+        config.upgrade_from_v1();
+        assert_eq!(config.admin, admin);
+        assert_eq!(config.oracle, oracle);
+        assert_eq!(config.current_epoch, 4);
+        assert_eq!(config.xp_to_sbt_ratio, 50);
+        assert_eq!(config.sbt_minted, 1_234);
+        assert_eq!(config.bump, 254);
+        assert_eq!(config.version, CONFIG_VERSION);
+        assert_eq!(config.xp_weighting, XpWeighting::Cumulative);
+        assert_eq!(config.epoch_duration_secs, SECONDS_PER_WEEK);
+        assert_eq!(config.max_xp_increase, MAX_XP_INCREASE);
+        assert_eq!(config.claim_window_secs, CLAIM_WINDOW_DAYS);
+        assert_eq!(config.retroactive_window_secs, RETROACTIVE_WINDOW_SECS);
+        assert_eq!(serialized_len(&config), Config::SPACE);
+    }
+
+    #[test]
+    fn migrates_v1_contributor_with_stale_username_bytes() {
+        let wallet = Pubkey::new_unique();
+        let data = grown_v1_account(
+            Contributor::DISCRIMINATOR,
+            &ContributorV1 {
+                wallet,
+                github_username: "bob".to_string(),
+                total_xp: 4_500,
+                total_sbt_claimable: 450_000,
+                total_sbt_claimed: 100_000,
+                lifetime_usdc_earned: 7_000,
+                last_claim_epoch: 3,
+                registered_at: 1_700_000_000,
+                bump: 253,
+            },
+            Contributor::V1_SPACE,
+            0xAA,
+            Contributor::SPACE,
+        );
+
+        let mut contributor = Contributor::try_deserialize(&mut &data[..]).unwrap();
+        contributor.upgrade_from_v1();
+        assert_eq!(contributor.wallet, wallet);
+        assert_eq!(contributor.github_username, "bob");
+        assert_eq!(contributor.total_xp, 4_500);
+        assert_eq!(contributor.total_sbt_claimed, 100_000);
+        assert_eq!(contributor.registered_at, 1_700_000_000);
+        assert_eq!(contributor.bump, 253);
+        assert_eq!(contributor.version, CONTRIBUTOR_VERSION);
+        assert_eq!(contributor.last_attestation_nonce, 0);
+        assert_eq!(contributor.last_synced_at, 0);
+        assert_eq!(contributor.payout_owner(i64::MAX), wallet);
+        assert_eq!(contributor.reserved, [0u8; CONTRIBUTOR_RESERVED_LEN]);
+    }
+
+    #[test]
+    fn migrates_v1_snapshot() {
+        let wallet = Pubkey::new_unique();
+        let oracle = Pubkey::new_unique();
+        let data = grown_v1_account(
+            EpochSnapshot::DISCRIMINATOR,
+            &EpochSnapshotV1 {
+                contributor: wallet,
+                epoch: 2,
+                xp: 4_500,
+                usdc_claimed: true,
+                sbt_earned: 450_000,
+                bump: 252,
+            },
+            EpochSnapshot::V1_SPACE,
+            0,
+            EpochSnapshot::SPACE,
+        );
+
+        let mut snapshot = EpochSnapshot::try_deserialize(&mut &data[..]).unwrap();
+        snapshot.upgrade_from_v1(oracle);
+        assert_eq!(snapshot.contributor, wallet);
+        assert_eq!(snapshot.epoch, 2);
+        assert_eq!(snapshot.xp, 4_500);
+        assert!(snapshot.usdc_claimed);
+        assert_eq!(snapshot.sbt_earned, 450_000);
+        assert_eq!(snapshot.bump, 252);
+        assert_eq!(snapshot.version, SNAPSHOT_VERSION);
+        assert_eq!(snapshot.baseline_xp, 0);
+        assert_eq!(snapshot.funder, oracle);
+        assert_eq!(serialized_len(&snapshot), EpochSnapshot::SPACE);
+    }
+
+    #[test]
+    fn migrates_v1_epoch() {
+        let v1 = RewardEpochV1 {
+            epoch_number: 3,
+            start_time: 1_700_000_000,
+            end_time: 1_700_604_800,
+            total_xp: 12_000,
+            usdc_reward_amount: 1_000_000_000,
+            contributor_count: 4,
+            finalized: true,
+            bump: 251,
+        };
+        let data = grown_v1_account(
+            RewardEpoch::DISCRIMINATOR,
+            &v1,
+            RewardEpoch::V1_SPACE,
+            0,
+            RewardEpoch::space(0),
+        );
+
+        let mut epoch = RewardEpoch::try_deserialize(&mut &data[..]).unwrap();
+        assert!(epoch.upgrade_from_v1(v1.usdc_reward_amount + 1, 4).is_err());
+
+        epoch.upgrade_from_v1(400_000_000, 5).unwrap();
+        assert_eq!(epoch.epoch_number, 3);
+        assert_eq!(epoch.start_time, 1_700_000_000);
+        assert_eq!(epoch.end_time, 1_700_604_800);
+        assert_eq!(epoch.total_xp, 12_000);
+        assert_eq!(epoch.usdc_reward_amount, 1_000_000_000);
+        assert_eq!(epoch.contributor_count, 4);
+        assert!(epoch.finalized);
+        assert_eq!(epoch.bump, 251);
+        assert_eq!(epoch.version, EPOCH_VERSION);
+        assert_eq!(epoch.xp_weighting, XpWeighting::Cumulative);
+        assert_eq!(epoch.merkle_leaf_count, 0);
+        assert_eq!(epoch.usdc_claimed_total, 400_000_000);
+        assert_eq!(epoch.snapshot_count, 5);
+        assert_eq!(epoch.claim_window_secs, CLAIM_WINDOW_DAYS);
+        assert_eq!(epoch.retroactive_window_secs, RETROACTIVE_WINDOW_SECS);
+        assert!(epoch.claim_bitmap.is_empty());
+        assert_eq!(serialized_len(&epoch), RewardEpoch::space(0));
+    }
+
+    fn expired_epoch(reward: u64, claimed: u64) -> RewardEpoch {
+        let mut epoch: RewardEpoch = zeroed(RewardEpoch::space(0));
+        epoch.end_time = 1_000;
+        epoch.claim_window_secs = CLAIM_WINDOW_DAYS;
+        epoch.retroactive_window_secs = RETROACTIVE_WINDOW_SECS;
+        epoch.usdc_reward_amount = reward;
+        epoch.finalized = true;
+        epoch.record_claim(claimed).unwrap();
+        epoch
+    }
+
+    #[test]
+    fn sweep_rolls_unclaimed_rewards_into_the_next_epoch() {
+        let mut epoch = expired_epoch(1_000, 400);
+        let mut next_epoch: RewardEpoch = zeroed(RewardEpoch::space(0));
+        next_epoch.usdc_reward_amount = 2_000;
+
+        assert!(epoch.sweep(epoch.claim_deadline()).is_err());
+        let remainder = epoch.sweep(epoch.claim_deadline() + 1).unwrap();
+        next_epoch.add_rollover(remainder).unwrap();
+
+        assert_eq!(remainder, 600);
+        assert_eq!(epoch.usdc_swept, 600);
+        assert!(epoch.swept);
+        assert_eq!(next_epoch.usdc_reward_amount, 2_600);
+        assert!(epoch.record_claim(1).is_err());
+        assert!(epoch.sweep(i64::MAX).is_err());
+
+        next_epoch.finalized = true;
+        assert!(next_epoch.add_rollover(1).is_err());
+    }
+
+    #[test]
+    fn sweep_to_treasury_releases_the_commitment() {
+        let mut config: Config = zeroed(Config::SPACE);
+        config.usdc_committed = 3_000;
+        let mut epoch = expired_epoch(1_000, 250);
+
+        let remainder = epoch.sweep(epoch.claim_deadline() + 1).unwrap();
+        config.release_commitment(remainder).unwrap();
+
+        assert_eq!(remainder, 750);
+        assert_eq!(config.usdc_committed, 2_250);
+        assert!(config.release_commitment(2_251).is_err());
+    }
+
+    #[test]
+    fn epochs_close_only_once_swept_and_emptied() {
+        let mut epoch = expired_epoch(1_000, 1_000);
+        epoch.snapshot_count = 1;
+        let after_deadline = epoch.claim_deadline() + 1;
+
+        assert_eq!(
+            epoch.require_closable(epoch.claim_deadline()).unwrap_err(),
+            error!(ErrorCode::ClaimWindowNotExpired)
+        );
+        assert_eq!(
+            epoch.require_closable(after_deadline).unwrap_err(),
+            error!(ErrorCode::EpochNotSwept)
+        );
+
+        epoch.sweep(after_deadline).unwrap();
+        assert_eq!(
+            epoch.require_closable(after_deadline).unwrap_err(),
+            error!(ErrorCode::SnapshotsStillOpen)
+        );
+
+        epoch.snapshot_count = 0;
+        epoch.require_closable(after_deadline).unwrap();
+    }
 }
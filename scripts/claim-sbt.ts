@@ -3,10 +3,12 @@ import { Program, AnchorProvider } from '@coral-xyz/anchor';
 import { 
   Connection, 
   PublicKey,
+  SystemProgram,
 } from '@solana/web3.js';
 import { 
   TOKEN_2022_PROGRAM_ID,
-  getOrCreateAssociatedTokenAccount,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  getAssociatedTokenAddressSync,
 } from '@solana/spl-token';
 import * as fs from 'fs';
 import * as path from 'path';
@@ -79,19 +81,14 @@ async function claimSbt() {
       return;
     }
     
-    console.log('\nGetting contributor SBT account...');
-    const contributorSbtAccount = await getOrCreateAssociatedTokenAccount(
-      connection,
-      contributorKeypair,
+    const contributorSbtAccount = getAssociatedTokenAddressSync(
       sbtMint,
       contributorKeypair.publicKey,
       false,
-      undefined,
-      undefined,
       TOKEN_2022_PROGRAM_ID
     );
     
-    console.log(`Contributor SBT Account: ${contributorSbtAccount.address.toString()}`);
+    console.log(`Contributor SBT Account: ${contributorSbtAccount.toString()}`);
     
     console.log('\nClaiming SBT tokens...');
     const tx = await program.methods
@@ -100,9 +97,11 @@ async function claimSbt() {
         config: configPda,
         contributor: contributorPda,
         sbtMint: sbtMint,
-        contributorSbtAccount: contributorSbtAccount.address,
+        contributorSbtAccount: contributorSbtAccount,
         wallet: contributorKeypair.publicKey,
         token2022Program: TOKEN_2022_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
       })
       .rpc();
     
@@ -112,7 +111,7 @@ async function claimSbt() {
     const updatedContributor = await program.account.contributor.fetch(contributorPda);
     console.log(`\nUpdated SBT Claimed: ${updatedContributor.totalSbtClaimed}`);
     
-    const sbtBalance = await connection.getTokenAccountBalance(contributorSbtAccount.address);
+    const sbtBalance = await connection.getTokenAccountBalance(contributorSbtAccount);
     console.log(`SBT Balance: ${sbtBalance.value.amount} SENDO`);
     
     console.log('\n💡 Remember: SENDO tokens are non-transferable governance tokens!');
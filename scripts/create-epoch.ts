@@ -15,9 +15,11 @@ async function createEpoch() {
   const args = process.argv.slice(2);
   const rewardAmountArg = args[0] || '100000000000000';
   const rewardAmount = new BN(rewardAmountArg);
+  const startTime = args[1] ? new BN(args[1]) : null;
   
   console.log(`\n=== Creating New Epoch ===\n`);
   console.log(`Reward Amount: ${rewardAmount.toString()} tokens`);
+  console.log(`Start Time: ${startTime ? startTime.toString() : 'now'}`);
 
   const connection = new Connection(DEVNET_RPC, 'confirmed');
   
@@ -63,12 +65,24 @@ async function createEpoch() {
   );
   console.log(`Epoch PDA: ${epochPda.toString()}`);
   
+  const previousEpochPda = config.currentEpoch > 0
+    ? PublicKey.findProgramAddressSync(
+        [
+          Buffer.from('epoch'),
+          new BN(config.currentEpoch).toArrayLike(Buffer, 'le', 8)
+        ],
+        programId
+      )[0]
+    : null;
+  
   console.log('\nCreating epoch...');
   const tx = await program.methods
-    .createEpoch(rewardAmount)
+    .createEpoch(rewardAmount, startTime)
     .accounts({
       config: configPda,
       epoch: epochPda,
+      previousEpoch: previousEpochPda,
+      usdcVault: config.usdcVault,
       oracle: oracleKeypair.publicKey,
       systemProgram: SystemProgram.programId,
     })
@@ -6,7 +6,6 @@ import {
   LAMPORTS_PER_SOL
 } from '@solana/web3.js';
 import { 
-  TOKEN_PROGRAM_ID,
   getAssociatedTokenAddress,
   getOrCreateAssociatedTokenAccount,
 } from '@solana/spl-token';
@@ -65,7 +64,10 @@ async function fundUsdc() {
   const usdcMint = config.usdcMint;
   const usdcVault = config.usdcVault;
   
+  const usdcTokenProgram = (await connection.getAccountInfo(usdcMint))!.owner;
+  
   console.log(`USDC Mint: ${usdcMint.toString()}`);
+  console.log(`USDC Token Program: ${usdcTokenProgram.toString()}`);
   console.log(`USDC Vault: ${usdcVault.toString()}`);
   
   console.log('\nGetting admin USDC account...');
@@ -73,7 +75,11 @@ async function fundUsdc() {
     connection,
     adminKeypair,
     usdcMint,
-    adminKeypair.publicKey
+    adminKeypair.publicKey,
+    false,
+    undefined,
+    undefined,
+    usdcTokenProgram
   );
   
   console.log(`Admin USDC Account: ${adminUsdcAccount.address.toString()}`);
@@ -95,9 +101,10 @@ async function fundUsdc() {
     .accounts({
       config: configPda,
       usdcVault: usdcVault,
+      usdcMint: usdcMint,
       funder: adminKeypair.publicKey,
       funderTokenAccount: adminUsdcAccount.address,
-      tokenProgram: TOKEN_PROGRAM_ID,
+      tokenProgram: usdcTokenProgram,
     })
     .rpc();
   
@@ -8,10 +8,8 @@ import {
   LAMPORTS_PER_SOL
 } from '@solana/web3.js';
 import { 
-  TOKEN_PROGRAM_ID,
   TOKEN_2022_PROGRAM_ID,
   getAssociatedTokenAddress,
-  createMint,
   getMint,
   getOrCreateAssociatedTokenAccount,
   ExtensionType,
@@ -20,8 +18,7 @@ import * as fs from 'fs';
 import * as path from 'path';
 import { 
   loadOrGenerateKeypair, 
-  displayKeypairInfo, 
-  saveKeypairToFile 
+  displayKeypairInfo 
 } from './utils';
 
 const DEVNET_RPC = 'https://api.devnet.solana.com';
@@ -95,43 +92,23 @@ async function initialize() {
   );
   console.log(`Config PDA: ${configPda.toString()}`);
   
+  const [sbtMintPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from('sbt_mint')],
+    programId
+  );
+  console.log(`SBT Mint PDA: ${sbtMintPda.toString()}`);
+  
   const usdcMint = cluster === 'mainnet' ? USDC_MINT_MAINNET : USDC_MINT_DEVNET;
   console.log(`USDC Mint: ${usdcMint.toString()}`);
   
-  console.log('\n=== Creating SBT Token (Token-2022 with NonTransferable) ===');
-  
-  const sbtMintKeypair = Keypair.generate();
-  console.log(`SBT Mint will be: ${sbtMintKeypair.publicKey.toString()}`);
-  
-  try {
-    const sbtMint = await createMint(
-      connection,
-      adminKeypair,
-      configPda,
-      null,
-      0,
-      sbtMintKeypair,
-      undefined,
-      TOKEN_2022_PROGRAM_ID
-    );
-    
-    console.log(`✅ SBT Token created: ${sbtMint.toString()}`);
-    console.log('   Name: Sendo Governance Token');
-    console.log('   Symbol: SENDO');
-    console.log('   Decimals: 0');
-    console.log('   Max Supply: 1,000,000,000');
-    console.log('   Mint Authority: Config PDA (controlled minting)');
-    console.log('   Transfer: NON-TRANSFERABLE (Token-2022)');
-    
-  } catch (error) {
-    console.error('❌ Failed to create SBT token:', error);
-    throw error;
-  }
+  const usdcTokenProgram = (await connection.getAccountInfo(usdcMint))!.owner;
+  console.log(`USDC Token Program: ${usdcTokenProgram.toString()}`);
   
   const usdcVault = await getAssociatedTokenAddress(
     usdcMint,
     configPda,
-    true
+    true,
+    usdcTokenProgram
   );
   console.log(`USDC Vault: ${usdcVault.toString()}`);
   
@@ -142,14 +119,15 @@ async function initialize() {
       .initialize(
         oracleKeypair.publicKey,
         usdcMint,
-        sbtMintKeypair.publicKey
+        PublicKey.default
       )
       .accounts({
         config: configPda,
         usdcVault: usdcVault,
         usdcMintAccount: usdcMint,
+        sbtMintAccount: null,
         admin: adminKeypair.publicKey,
-        tokenProgram: TOKEN_PROGRAM_ID,
+        tokenProgram: usdcTokenProgram,
         associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       })
@@ -158,6 +136,28 @@ async function initialize() {
     console.log(`\n✅ Program initialized successfully!`);
     console.log(`Transaction: ${tx}`);
     
+    console.log('\n=== Creating SBT Token (Token-2022 with NonTransferable) ===');
+    
+    const sbtMintTx = await program.methods
+      .createSbtMint('Sendo Governance Token', 'SENDO', process.env.SBT_METADATA_URI ?? '')
+      .accounts({
+        config: configPda,
+        sbtMint: sbtMintPda,
+        admin: adminKeypair.publicKey,
+        token2022Program: TOKEN_2022_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    
+    console.log(`✅ SBT Token created: ${sbtMintPda.toString()}`);
+    console.log(`Transaction: ${sbtMintTx}`);
+    console.log('   Name: Sendo Governance Token');
+    console.log('   Symbol: SENDO');
+    console.log('   Decimals: 0');
+    console.log('   Max Supply: 1,000,000,000');
+    console.log('   Mint Authority: Config PDA (controlled minting)');
+    console.log('   Transfer: NON-TRANSFERABLE (Token-2022)');
+    
     const config = await program.account.config.fetch(configPda);
     console.log('\n=== Config Details ===');
     console.log(`  Admin: ${config.admin.toString()}`);
@@ -183,7 +183,7 @@ ORACLE_PRIVATE_KEY=${base58PrivateKey}
 
 CONFIG_PDA=${configPda.toString()}
 USDC_MINT=${usdcMint.toString()}
-SBT_MINT=${sbtMintKeypair.publicKey.toString()}
+SBT_MINT=${sbtMintPda.toString()}
 USDC_VAULT=${usdcVault.toString()}
 
 # Leaderboard Data Source
@@ -208,9 +208,6 @@ LOG_FILE=oracle-bot.log
     fs.writeFileSync(envPath, envContent.trim());
     console.log(`\n✅ Oracle bot configuration saved to ${envPath}`);
     
-    saveKeypairToFile(sbtMintKeypair, path.join(__dirname, '../keys/sbt-mint.json'));
-    console.log(`✅ SBT mint keypair saved to keys/sbt-mint.json`);
-    
     console.log('\n=== Initialization Complete ===\n');
     console.log('📋 Next Steps:');
     console.log('1. Fund the USDC vault: yarn fund <amount>');
@@ -5,17 +5,90 @@ import {
   PublicKey, 
   Keypair, 
   SystemProgram,
+  Transaction,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+  Ed25519Program,
   LAMPORTS_PER_SOL 
 } from "@solana/web3.js";
 import {
   TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
   ASSOCIATED_TOKEN_PROGRAM_ID,
+  ExtensionType,
+  getExtensionTypes,
+  getMint,
+  getTokenMetadata,
   getAssociatedTokenAddress,
+  getAssociatedTokenAddressSync,
+  getAccount,
   createAssociatedTokenAccount,
   createMint,
   mintTo,
 } from "@solana/spl-token";
 import { assert } from "chai";
+import { createHash } from "crypto";
+
+function merkleLeaf(index: number, wallet: PublicKey, xp: BN, amount: BN): Buffer {
+  const indexBuf = Buffer.alloc(4);
+  indexBuf.writeUInt32LE(index);
+  return createHash("sha256")
+    .update(Buffer.from([0]))
+    .update(indexBuf)
+    .update(wallet.toBuffer())
+    .update(xp.toArrayLike(Buffer, "le", 8))
+    .update(amount.toArrayLike(Buffer, "le", 8))
+    .digest();
+}
+
+function hashPair(a: Buffer, b: Buffer): Buffer {
+  const [left, right] = Buffer.compare(a, b) <= 0 ? [a, b] : [b, a];
+  return createHash("sha256").update(Buffer.from([1])).update(left).update(right).digest();
+}
+
+function unregisteredXpLeaf(wallet: PublicKey, xp: BN): Buffer {
+  return createHash("sha256")
+    .update(Buffer.from([2]))
+    .update(wallet.toBuffer())
+    .update(xp.toArrayLike(Buffer, "le", 8))
+    .digest();
+}
+
+function merkleLayers(leaves: Buffer[]): Buffer[][] {
+  const layers = [leaves];
+  while (layers[layers.length - 1].length > 1) {
+    const prev = layers[layers.length - 1];
+    const next: Buffer[] = [];
+    for (let i = 0; i < prev.length; i += 2) {
+      next.push(i + 1 < prev.length ? hashPair(prev[i], prev[i + 1]) : prev[i]);
+    }
+    layers.push(next);
+  }
+  return layers;
+}
+
+function merkleRoot(leaves: Buffer[]): Buffer {
+  const layers = merkleLayers(leaves);
+  return layers[layers.length - 1][0];
+}
+
+function merkleProof(leaves: Buffer[], index: number): Buffer[] {
+  const proof: Buffer[] = [];
+  for (const layer of merkleLayers(leaves).slice(0, -1)) {
+    const sibling = index ^ 1;
+    if (sibling < layer.length) {
+      proof.push(layer[sibling]);
+    }
+    index = Math.floor(index / 2);
+  }
+  return proof;
+}
+
+// Short epochs let the suite finalize and roll over epochs without a warped clock.
+const EPOCH_DURATION_SECS = 2;
+
+const SBT_NAME = "Sendo Governance Token";
+const SBT_SYMBOL = "SENDO";
+const SBT_URI = "https://example.com/sendo.json";
 
 describe("leaderboard-rewards", () => {
   const provider = anchor.AnchorProvider.env();
@@ -28,11 +101,11 @@ describe("leaderboard-rewards", () => {
   const contributor1 = Keypair.generate();
   const contributor2 = Keypair.generate();
   const contributor3 = Keypair.generate();
+  const coldWallet = Keypair.generate();
 
   let configPda: PublicKey;
   let usdcMint: Keypair;
   let usdcMintAddress: PublicKey;
-  let sbtMint: Keypair;
   let sbtMintAddress: PublicKey;
   let usdcVault: PublicKey;
   let contributor1UsdcAccount: PublicKey;
@@ -75,6 +148,25 @@ describe("leaderboard-rewards", () => {
     );
   }
 
+  async function waitForLastEpochEnd() {
+    const config = await program.account.config.fetch(configPda);
+    const waitMs = (config.lastEpochEnd.toNumber() + 1) * 1000 - Date.now();
+    if (waitMs > 0) {
+      await new Promise(resolve => setTimeout(resolve, waitMs));
+    }
+  }
+
+  // Epoch 3 pays bob through a Merkle distribution instead of snapshots.
+  const MERKLE_XP = new BN(5000);
+  const MERKLE_AMOUNT = new BN(300000000);
+
+  function merkleEpochLeaves(): Buffer[] {
+    return [
+      merkleLeaf(0, contributor2.publicKey, MERKLE_XP, MERKLE_AMOUNT),
+      merkleLeaf(1, contributor3.publicKey, new BN(2000), new BN(200000000)),
+    ];
+  }
+
   before(async () => {
     const airdropTx1 = await provider.connection.requestAirdrop(
       oracleKeypair.publicKey,
@@ -114,16 +206,6 @@ describe("leaderboard-rewards", () => {
       usdcMint
     );
     
-    sbtMint = Keypair.generate();
-    sbtMintAddress = await createMint(
-      provider.connection,
-      admin,
-      admin.publicKey,
-      null,
-      9,
-      sbtMint
-    );
-    
     usdcVault = await getAssociatedTokenAddress(
       usdcMintAddress,
       configPda,
@@ -131,11 +213,12 @@ describe("leaderboard-rewards", () => {
     );
 
     await program.methods
-      .initialize(oracleKeypair.publicKey, usdcMintAddress, sbtMintAddress)
+      .initialize(oracleKeypair.publicKey, usdcMintAddress, PublicKey.default)
       .accounts({
         config: configPda,
         usdcVault: usdcVault,
         usdcMintAccount: usdcMintAddress,
+        sbtMintAccount: null,
         admin: admin.publicKey,
         tokenProgram: TOKEN_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
@@ -147,11 +230,78 @@ describe("leaderboard-rewards", () => {
     assert.equal(config.admin.toString(), admin.publicKey.toString());
     assert.equal(config.oracle.toString(), oracleKeypair.publicKey.toString());
     assert.equal(config.usdcMint.toString(), usdcMintAddress.toString());
-    assert.equal(config.sbtMint.toString(), sbtMintAddress.toString());
+    assert.equal(config.sbtMint.toString(), PublicKey.default.toString());
     assert.equal(config.currentEpoch, 0);
     assert.equal(config.totalEpochs, 0);
   });
 
+  it("Creates the soulbound SBT mint", async () => {
+    [sbtMintAddress] = PublicKey.findProgramAddressSync(
+      [Buffer.from("sbt_mint")],
+      program.programId
+    );
+
+    // The mint lives at a fixed address, so anyone can fund it before the admin creates it.
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: admin.publicKey,
+          toPubkey: sbtMintAddress,
+          lamports: 1,
+        })
+      )
+    );
+
+    await program.methods
+      .createSbtMint(SBT_NAME, SBT_SYMBOL, SBT_URI)
+      .accounts({
+        config: configPda,
+        sbtMint: sbtMintAddress,
+        admin: admin.publicKey,
+        token2022Program: TOKEN_2022_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    const config = await program.account.config.fetch(configPda);
+    assert.equal(config.sbtMint.toString(), sbtMintAddress.toString());
+
+    const mint = await getMint(
+      provider.connection,
+      sbtMintAddress,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    assert.equal(mint.decimals, 0);
+    assert.equal(mint.mintAuthority.toString(), configPda.toString());
+    const extensions = getExtensionTypes(mint.tlvData);
+    assert.include(extensions, ExtensionType.NonTransferable);
+    assert.include(extensions, ExtensionType.MetadataPointer);
+    assert.include(extensions, ExtensionType.TokenMetadata);
+
+    const metadata = await getTokenMetadata(provider.connection, sbtMintAddress);
+    assert.equal(metadata.name, SBT_NAME);
+    assert.equal(metadata.symbol, SBT_SYMBOL);
+    assert.equal(metadata.uri, SBT_URI);
+    assert.equal(metadata.updateAuthority.toString(), configPda.toString());
+
+    try {
+      await program.methods
+        .createSbtMint(SBT_NAME, SBT_SYMBOL, SBT_URI)
+        .accounts({
+          config: configPda,
+          sbtMint: sbtMintAddress,
+          admin: admin.publicKey,
+          token2022Program: TOKEN_2022_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+      assert.fail("Should have rejected a second SBT mint");
+    } catch (err) {
+      assert.include(err.toString(), "SbtMintAlreadySet");
+    }
+  });
+
   it("Updates oracle", async () => {
     const newOracle = Keypair.generate();
 
@@ -177,15 +327,265 @@ describe("leaderboard-rewards", () => {
       .rpc();
   });
 
+  it("Transfers admin in two steps", async () => {
+    const newAdmin = Keypair.generate();
+
+    await program.methods
+      .proposeAdmin(newAdmin.publicKey)
+      .accounts({
+        config: configPda,
+        admin: admin.publicKey,
+      })
+      .rpc();
+
+    let config = await program.account.config.fetch(configPda);
+    assert.equal(config.pendingAdmin.toString(), newAdmin.publicKey.toString());
+    assert.equal(config.admin.toString(), admin.publicKey.toString());
+
+    try {
+      await program.methods
+        .acceptAdmin()
+        .accounts({
+          config: configPda,
+          pendingAdmin: contributor1.publicKey,
+        })
+        .signers([contributor1])
+        .rpc();
+
+      assert.fail("Only the pending admin may accept");
+    } catch (error: any) {
+      assert.include(error.message, "NotPendingAdmin");
+    }
+
+    await program.methods
+      .acceptAdmin()
+      .accounts({
+        config: configPda,
+        pendingAdmin: newAdmin.publicKey,
+      })
+      .signers([newAdmin])
+      .rpc();
+
+    await program.methods
+      .proposeAdmin(admin.publicKey)
+      .accounts({
+        config: configPda,
+        admin: newAdmin.publicKey,
+      })
+      .signers([newAdmin])
+      .rpc();
+
+    await program.methods
+      .acceptAdmin()
+      .accounts({
+        config: configPda,
+        pendingAdmin: admin.publicKey,
+      })
+      .rpc();
+
+    config = await program.account.config.fetch(configPda);
+    assert.equal(config.admin.toString(), admin.publicKey.toString());
+    assert.equal(config.pendingAdmin.toString(), PublicKey.default.toString());
+  });
+
+  it("Funds reward pool", async () => {
+    const adminTokenAccount = await getAssociatedTokenAddress(
+      usdcMintAddress,
+      admin.publicKey
+    );
+
+    await createAssociatedTokenAccount(
+      provider.connection,
+      admin,
+      usdcMintAddress,
+      admin.publicKey
+    );
+    
+    const mintAmount = new BN(10000000000);
+    await mintTo(
+      provider.connection,
+      admin,
+      usdcMintAddress,
+      adminTokenAccount,
+      admin,
+      BigInt(mintAmount.toString())
+    );
+
+    const fundAmount = new BN(5000000000);
+    await program.methods
+      .fundUsdcPool(fundAmount)
+      .accounts({
+        config: configPda,
+        usdcVault: usdcVault,
+        usdcMint: usdcMintAddress,
+        funder: admin.publicKey,
+        funderTokenAccount: adminTokenAccount,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    const vaultBalance = await provider.connection.getTokenAccountBalance(usdcVault);
+    assert.equal(vaultBalance.value.amount, fundAmount.toString());
+  });
+
+  it("Pauses and resumes pool funding", async () => {
+    const PAUSE_FUND_POOL = 1 << 4;
+    const adminTokenAccount = await getAssociatedTokenAddress(
+      usdcMintAddress,
+      admin.publicKey
+    );
+
+    await program.methods
+      .setPause(PAUSE_FUND_POOL)
+      .accounts({
+        config: configPda,
+        admin: admin.publicKey,
+      })
+      .rpc();
+
+    try {
+      await program.methods
+        .fundUsdcPool(new BN(1))
+        .accounts({
+          config: configPda,
+          usdcVault: usdcVault,
+          usdcMint: usdcMintAddress,
+          funder: admin.publicKey,
+          funderTokenAccount: adminTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+      assert.fail("Funding should be paused");
+    } catch (error: any) {
+      assert.include(error.message, "ProgramPaused");
+    }
+
+    await program.methods
+      .setPause(0)
+      .accounts({
+        config: configPda,
+        admin: admin.publicKey,
+      })
+      .rpc();
+
+    const config = await program.account.config.fetch(configPda);
+    assert.equal(config.pausedFlags, 0);
+  });
+
+  it("Sets the epoch duration", async () => {
+    try {
+      await program.methods
+        .setEpochDuration(new BN(0))
+        .accounts({
+          config: configPda,
+          admin: admin.publicKey,
+        })
+        .rpc();
+
+      assert.fail("Should have rejected a zero duration");
+    } catch (error: any) {
+      assert.include(error.message, "InvalidEpochDuration");
+    }
+
+    await program.methods
+      .setEpochDuration(new BN(EPOCH_DURATION_SECS))
+      .accounts({
+        config: configPda,
+        admin: admin.publicKey,
+      })
+      .rpc();
+
+    const config = await program.account.config.fetch(configPda);
+    assert.equal(config.epochDurationSecs.toNumber(), EPOCH_DURATION_SECS);
+  });
+
+  it("Updates protocol parameters", async () => {
+    const params = {
+      maxXpIncrease: new BN(2_000_000),
+      claimWindowSecs: new BN(30 * 24 * 60 * 60),
+      retroactiveWindowSecs: new BN(90 * 24 * 60 * 60),
+      xpToSbtRatio: new BN(100),
+      maxXpPerEpoch: new BN(0),
+      maxSbtPerEpoch: new BN(0),
+      minSyncIntervalSecs: new BN(0),
+    };
+
+    try {
+      await program.methods
+        .updateParams({ ...params, retroactiveWindowSecs: new BN(24 * 60 * 60) })
+        .accounts({
+          config: configPda,
+          admin: admin.publicKey,
+        })
+        .rpc();
+
+      assert.fail("Should have rejected a retroactive window shorter than the claim window");
+    } catch (error: any) {
+      assert.include(error.message, "InvalidParams");
+    }
+
+    try {
+      // At the maximum ratio this increase would overflow the SBT earned by one sync
+      await program.methods
+        .updateParams({ ...params, maxXpIncrease: new BN("18446744073710") })
+        .accounts({
+          config: configPda,
+          admin: admin.publicKey,
+        })
+        .rpc();
+
+      assert.fail("Should have rejected an XP increase whose SBT could overflow");
+    } catch (error: any) {
+      assert.include(error.message, "InvalidParams");
+    }
+
+    await program.methods
+      .updateParams(params)
+      .accounts({
+        config: configPda,
+        admin: admin.publicKey,
+      })
+      .rpc();
+
+    const config = await program.account.config.fetch(configPda);
+    assert.equal(config.maxXpIncrease.toString(), "2000000");
+    assert.equal(config.claimWindowSecs.toString(), params.claimWindowSecs.toString());
+    assert.equal(config.retroactiveWindowSecs.toString(), params.retroactiveWindowSecs.toString());
+    assert.equal(config.xpToSbtRatio.toString(), "100");
+  });
+
+  it("Rejects migrating a config already on the current layout", async () => {
+    const config = await program.account.config.fetch(configPda);
+    assert.equal(config.version, 2);
+
+    try {
+      await program.methods
+        .migrateConfig()
+        .accounts({
+          config: configPda,
+          admin: admin.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+
+      assert.fail("Should have rejected a current-layout config");
+    } catch (error: any) {
+      assert.include(error.message, "AlreadyMigrated");
+    }
+  });
+
   it("Creates first epoch", async () => {
     const [epochPda] = findEpochPda(1);
     const rewardAmount = new BN(1000000000);
 
     await program.methods
-      .createEpoch(rewardAmount)
+      .createEpoch(rewardAmount, null)
       .accounts({
         config: configPda,
         epoch: epochPda,
+        previousEpoch: null,
+        usdcVault: usdcVault,
         oracle: oracleKeypair.publicKey,
         systemProgram: SystemProgram.programId,
       })
@@ -202,6 +602,33 @@ describe("leaderboard-rewards", () => {
     assert.equal(epoch.totalXp.toString(), "0");
     assert.equal(epoch.contributorCount, 0);
     assert.equal(epoch.finalized, false);
+    assert.equal(config.usdcCommitted.toString(), rewardAmount.toString());
+    assert.equal(epoch.endTime.sub(epoch.startTime).toNumber(), EPOCH_DURATION_SECS);
+    assert.equal(config.lastEpochEnd.toString(), epoch.endTime.toString());
+  });
+
+  it("Rejects epochs overlapping the previous one", async () => {
+    const [epochPda] = findEpochPda(2);
+    const config = await program.account.config.fetch(configPda);
+
+    try {
+      await program.methods
+        .createEpoch(new BN(1), config.lastEpochEnd.subn(1))
+        .accounts({
+          config: configPda,
+          epoch: epochPda,
+          previousEpoch: findEpochPda(1)[0],
+          usdcVault: usdcVault,
+          oracle: oracleKeypair.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([oracleKeypair])
+        .rpc();
+
+      assert.fail("Should have rejected an overlapping epoch");
+    } catch (error: any) {
+      assert.include(error.message, "EpochOverlap");
+    }
   });
 
   it("Registers contributors", async () => {
@@ -262,6 +689,7 @@ describe("leaderboard-rewards", () => {
     assert.equal(snapshot.xp.toString(), "5000");
     assert.equal(snapshot.usdcClaimed, false);
     assert.equal(snapshot.sbtEarned.toString(), "500000");
+    assert.equal(snapshot.funder.toString(), oracleKeypair.publicKey.toString());
 
     const contributorData = await program.account.contributor.fetch(contributor1Pda);
     assert.equal(contributorData.totalXp.toString(), "5000");
@@ -346,6 +774,7 @@ describe("leaderboard-rewards", () => {
     const epoch = await program.account.rewardEpoch.fetch(epochPda);
     assert.equal(epoch.totalXp.toString(), "8000");
     assert.equal(epoch.contributorCount, 2);
+    assert.equal(epoch.snapshotCount, 3);
   });
 
   it("Updates existing contributor XP", async () => {
@@ -390,43 +819,44 @@ describe("leaderboard-rewards", () => {
     assert.equal(contributorData.totalSbtClaimable.toString(), "700000");
   });
 
-  it("Funds reward pool", async () => {
-    const adminTokenAccount = await getAssociatedTokenAddress(
-      usdcMintAddress,
-      admin.publicKey
-    );
+  it("Commits unregistered XP for the epoch", async () => {
+    const [epochPda] = findEpochPda(1);
+    const leaves = [
+      unregisteredXpLeaf(contributor3.publicKey, new BN(2000)),
+      unregisteredXpLeaf(Keypair.generate().publicKey, new BN(1000)),
+    ];
 
-    await createAssociatedTokenAccount(
-      provider.connection,
-      admin,
-      usdcMintAddress,
-      admin.publicKey
-    );
-    
-    const mintAmount = new BN(10000000000);
-    await mintTo(
-      provider.connection,
-      admin,
-      usdcMintAddress,
-      adminTokenAccount,
-      admin,
-      BigInt(mintAmount.toString())
-    );
-
-    const fundAmount = new BN(1000000000);
     await program.methods
-      .fundUsdcPool(fundAmount)
+      .commitUnregisteredXp(new BN(1), Array.from(merkleRoot(leaves)), new BN(3000))
       .accounts({
         config: configPda,
-        usdcVault: usdcVault,
-        funder: admin.publicKey,
-        funderTokenAccount: adminTokenAccount,
-        tokenProgram: TOKEN_PROGRAM_ID,
+        epoch: epochPda,
+        oracle: oracleKeypair.publicKey,
       })
+      .signers([oracleKeypair])
       .rpc();
 
-    const vaultBalance = await provider.connection.getTokenAccountBalance(usdcVault);
-    assert.equal(vaultBalance.value.amount, fundAmount.toString());
+    const epoch = await program.account.rewardEpoch.fetch(epochPda);
+    assert.equal(epoch.unregisteredTotalXp.toString(), "3000");
+    assert.equal(epoch.totalXp.toString(), "10000");
+  });
+
+  it("Registers a contributor previously synced while unregistered", async () => {
+    const [contributor3Pda] = findContributorPda(contributor3.publicKey);
+
+    await program.methods
+      .registerContributor("charlie")
+      .accounts({
+        contributor: contributor3Pda,
+        wallet: contributor3.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([contributor3])
+      .rpc();
+
+    const contributorData = await program.account.contributor.fetch(contributor3Pda);
+    assert.equal(contributorData.wallet.toString(), contributor3.publicKey.toString());
+    assert.equal(contributorData.githubUsername, "charlie");
   });
 
   it("Finalizes epoch after time passes", async () => {
@@ -443,11 +873,12 @@ describe("leaderboard-rewards", () => {
     }
 
     await program.methods
-      .finalizeEpoch(new BN(1))
+      .finalizeEpoch(new BN(1), null)
       .accounts({
         config: configPda,
         epoch: epochPda,
         oracle: oracleKeypair.publicKey,
+        systemProgram: SystemProgram.programId,
       })
       .signers([oracleKeypair])
       .rpc();
@@ -470,20 +901,13 @@ describe("leaderboard-rewards", () => {
       contributor1.publicKey
     );
 
-    await createAssociatedTokenAccount(
-      provider.connection,
-      contributor1,
-      usdcMintAddress,
-      contributor1.publicKey
-    );
-
     const [contributor1Pda] = findContributorPda(contributor1.publicKey);
     const [snapshot1Pda] = findSnapshotPda(1, contributor1.publicKey);
 
     const snapshotBefore = await program.account.epochSnapshot.fetch(snapshot1Pda);
     const expectedReward = snapshotBefore.xp
       .mul(epochData.usdcRewardAmount)
-      .div(epochData.totalXp);
+      .div(epochData.totalXp.add(epochData.unregisteredTotalXp));
 
     await program.methods
       .claimUsdcRewards(new BN(1))
@@ -493,9 +917,13 @@ describe("leaderboard-rewards", () => {
         snapshot: snapshot1Pda,
         contributor: contributor1Pda,
         usdcVault: usdcVault,
+        usdcMint: usdcMintAddress,
+        payoutOwner: contributor1.publicKey,
         contributorTokenAccount: contributor1UsdcAccount,
         wallet: contributor1.publicKey,
         tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
       })
       .signers([contributor1])
       .rpc();
@@ -509,6 +937,9 @@ describe("leaderboard-rewards", () => {
 
     const tokenBalance = await provider.connection.getTokenAccountBalance(contributor1UsdcAccount);
     assert.equal(tokenBalance.value.amount, expectedReward.toString());
+
+    const epochAfter = await program.account.rewardEpoch.fetch(epochPda);
+    assert.equal(epochAfter.usdcClaimedTotal.toString(), expectedReward.toString());
   });
 
   it("Prevents double claiming", async () => {
@@ -532,9 +963,13 @@ describe("leaderboard-rewards", () => {
           snapshot: snapshot1Pda,
           contributor: contributor1Pda,
           usdcVault: usdcVault,
+          usdcMint: usdcMintAddress,
+          payoutOwner: contributor1.publicKey,
           contributorTokenAccount: contributor1UsdcAccount,
           wallet: contributor1.publicKey,
           tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
         })
         .signers([contributor1])
         .rpc();
@@ -545,6 +980,178 @@ describe("leaderboard-rewards", () => {
     }
   });
 
+  it("Claims retroactive USDC for XP earned while unregistered", async () => {
+    const [epochPda] = findEpochPda(1);
+    const epochData = await program.account.rewardEpoch.fetch(epochPda);
+
+    if (!epochData.finalized) {
+      console.log("Skipping retroactive claim test - epoch not finalized");
+      return;
+    }
+
+    const contributor3UsdcAccount = await createAssociatedTokenAccount(
+      provider.connection,
+      contributor3,
+      usdcMintAddress,
+      contributor3.publicKey
+    );
+
+    const [contributor3Pda] = findContributorPda(contributor3.publicKey);
+    const [snapshot3Pda] = findSnapshotPda(1, contributor3.publicKey);
+    const xp = new BN(2000);
+    const leaves = [
+      unregisteredXpLeaf(contributor3.publicKey, xp),
+      unregisteredXpLeaf(Keypair.generate().publicKey, new BN(1000)),
+    ];
+    const expectedReward = xp
+      .mul(epochData.usdcRewardAmount)
+      .div(epochData.totalXp.add(epochData.unregisteredTotalXp));
+
+    await program.methods
+      .claimRetroactiveUsdc(new BN(1), xp, merkleProof(leaves, 0).map((node) => Array.from(node)))
+      .accounts({
+        config: configPda,
+        epoch: epochPda,
+        snapshot: snapshot3Pda,
+        contributor: contributor3Pda,
+        usdcVault: usdcVault,
+        usdcMint: usdcMintAddress,
+        payoutOwner: contributor3.publicKey,
+        contributorTokenAccount: contributor3UsdcAccount,
+        wallet: contributor3.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([contributor3])
+      .rpc();
+
+    const snapshot = await program.account.epochSnapshot.fetch(snapshot3Pda);
+    assert.equal(snapshot.usdcClaimed, true);
+    assert.equal(snapshot.xp.toString(), "2000");
+
+    const tokenBalance = await provider.connection.getTokenAccountBalance(contributor3UsdcAccount);
+    assert.equal(tokenBalance.value.amount, expectedReward.toString());
+  });
+
+  it("Rejects USDC claims into an account the wallet does not own", async () => {
+    const [epochPda] = findEpochPda(1);
+    const [contributor1Pda] = findContributorPda(contributor1.publicKey);
+    const [snapshot1Pda] = findSnapshotPda(1, contributor1.publicKey);
+    const contributor3UsdcAccount = getAssociatedTokenAddressSync(
+      usdcMintAddress,
+      contributor3.publicKey
+    );
+
+    try {
+      await program.methods
+        .claimUsdcRewards(new BN(1))
+        .accounts({
+          config: configPda,
+          epoch: epochPda,
+          snapshot: snapshot1Pda,
+          contributor: contributor1Pda,
+          usdcVault: usdcVault,
+          usdcMint: usdcMintAddress,
+          payoutOwner: contributor1.publicKey,
+          contributorTokenAccount: contributor3UsdcAccount,
+          wallet: contributor1.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([contributor1])
+        .rpc();
+
+      assert.fail("Should have rejected a destination owned by another wallet");
+    } catch (error: any) {
+      assert.include(error.message, "ConstraintTokenOwner");
+    }
+  });
+
+  it("Rejects Merkle claims on snapshot-mode epochs", async () => {
+    const [epochPda] = findEpochPda(1);
+    const epochData = await program.account.rewardEpoch.fetch(epochPda);
+
+    if (!epochData.finalized) {
+      console.log("Skipping Merkle claim test - epoch not finalized");
+      return;
+    }
+
+    const [contributor1Pda] = findContributorPda(contributor1.publicKey);
+    const amount = new BN(1000);
+    const leaf = merkleLeaf(0, contributor1.publicKey, new BN(7000), amount);
+
+    try {
+      await program.methods
+        .claimUsdcWithProof(new BN(1), 0, new BN(7000), amount, [Array.from(leaf)])
+        .accounts({
+          config: configPda,
+          epoch: epochPda,
+          contributor: contributor1Pda,
+          usdcVault: usdcVault,
+          usdcMint: usdcMintAddress,
+          payoutOwner: contributor1.publicKey,
+          contributorTokenAccount: contributor1UsdcAccount,
+          wallet: contributor1.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([contributor1])
+        .rpc();
+
+      assert.fail("Should have thrown error for missing Merkle distribution");
+    } catch (error: any) {
+      assert.include(error.message, "MerkleDistributionNotSet");
+    }
+  });
+
+  it("Stages payout address changes behind a timelock", async () => {
+    const [epochPda] = findEpochPda(1);
+    const [contributor1Pda] = findContributorPda(contributor1.publicKey);
+    const [snapshot1Pda] = findSnapshotPda(1, contributor1.publicKey);
+
+    await program.methods
+      .setPayoutAddress(coldWallet.publicKey)
+      .accounts({
+        contributor: contributor1Pda,
+        wallet: contributor1.publicKey,
+      })
+      .signers([contributor1])
+      .rpc();
+
+    const contributor = await program.account.contributor.fetch(contributor1Pda);
+    assert.equal(contributor.payoutAddress.toString(), PublicKey.default.toString());
+    assert.equal(contributor.pendingPayoutAddress.toString(), coldWallet.publicKey.toString());
+    assert.isAbove(contributor.payoutEffectiveAt.toNumber(), Math.floor(Date.now() / 1000));
+
+    try {
+      await program.methods
+        .claimUsdcRewards(new BN(1))
+        .accounts({
+          config: configPda,
+          epoch: epochPda,
+          snapshot: snapshot1Pda,
+          contributor: contributor1Pda,
+          usdcVault: usdcVault,
+          usdcMint: usdcMintAddress,
+          payoutOwner: coldWallet.publicKey,
+          contributorTokenAccount: getAssociatedTokenAddressSync(usdcMintAddress, coldWallet.publicKey),
+          wallet: contributor1.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([contributor1])
+        .rpc();
+
+      assert.fail("Should not pay the pending address before the timelock passes");
+    } catch (error: any) {
+      assert.include(error.message, "InvalidPayoutAddress");
+    }
+  });
+
   it("Updates GitHub link", async () => {
     const [contributor1Pda] = findContributorPda(contributor1.publicKey);
 
@@ -561,15 +1168,35 @@ describe("leaderboard-rewards", () => {
     assert.equal(contributorData.githubUsername, "alice_updated");
   });
 
+  it("Switches XP weighting to per-epoch deltas", async () => {
+    await program.methods
+      .setXpWeighting({ perEpoch: {} })
+      .accounts({
+        config: configPda,
+        admin: admin.publicKey,
+      })
+      .rpc();
+
+    const config = await program.account.config.fetch(configPda);
+    assert.deepEqual(config.xpWeighting, { perEpoch: {} });
+
+    const epoch = await program.account.rewardEpoch.fetch(findEpochPda(1)[0]);
+    assert.deepEqual(epoch.xpWeighting, { cumulative: {} });
+  });
+
   it("Creates second epoch", async () => {
     const [epochPda] = findEpochPda(2);
     const rewardAmount = new BN(2000000000);
 
+    await waitForLastEpochEnd();
+
     await program.methods
-      .createEpoch(rewardAmount)
+      .createEpoch(rewardAmount, null)
       .accounts({
         config: configPda,
         epoch: epochPda,
+        previousEpoch: findEpochPda(1)[0],
+        usdcVault: usdcVault,
         oracle: oracleKeypair.publicKey,
         systemProgram: SystemProgram.programId,
       })
@@ -579,5 +1206,820 @@ describe("leaderboard-rewards", () => {
     const config = await program.account.config.fetch(configPda);
     assert.equal(config.currentEpoch, 2);
     assert.equal(config.totalEpochs, 2);
+
+    const epoch = await program.account.rewardEpoch.fetch(epochPda);
+    assert.deepEqual(epoch.xpWeighting, { perEpoch: {} });
+    const previousEpoch = await program.account.rewardEpoch.fetch(findEpochPda(1)[0]);
+    assert.equal(previousEpoch.finalized, true);
+  });
+
+  it("Weights second epoch by XP earned within the epoch", async () => {
+    const [epochPda] = findEpochPda(2);
+    const [contributor2Pda] = findContributorPda(contributor2.publicKey);
+    const [snapshotPda] = findSnapshotPda(2, contributor2.publicKey);
+
+    await program.methods
+      .syncContributorXp(contributor2.publicKey, "bob", new BN(4500), [], [], [])
+      .accounts({
+        config: configPda,
+        epoch: epochPda,
+        contributor: contributor2Pda,
+        snapshot: snapshotPda,
+        oracle: oracleKeypair.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([oracleKeypair])
+      .rpc();
+
+    const snapshot = await program.account.epochSnapshot.fetch(snapshotPda);
+    assert.equal(snapshot.baselineXp.toString(), "3000");
+    assert.equal(snapshot.xp.toString(), "1500");
+
+    const epoch = await program.account.rewardEpoch.fetch(epochPda);
+    assert.equal(epoch.totalXp.toString(), "1500");
+  });
+
+  it("Batch syncs XP for several contributors in one transaction", async () => {
+    const [epochPda] = findEpochPda(2);
+    const unregistered = Keypair.generate().publicKey;
+    const entries = [
+      { wallet: contributor1.publicKey, totalXp: new BN(7600), githubUsername: "alice" },
+      { wallet: contributor2.publicKey, totalXp: new BN(4800), githubUsername: "bob" },
+      { wallet: unregistered, totalXp: new BN(900), githubUsername: "dave" },
+    ];
+
+    const remainingAccounts = entries.flatMap(({ wallet }) => [
+      { pubkey: findContributorPda(wallet)[0], isSigner: false, isWritable: true },
+      { pubkey: findSnapshotPda(2, wallet)[0], isSigner: false, isWritable: true },
+    ]);
+
+    // A lamport sent to a snapshot address ahead of time must not block its creation.
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: admin.publicKey,
+          toPubkey: findSnapshotPda(2, contributor1.publicKey)[0],
+          lamports: 1,
+        })
+      )
+    );
+
+    await program.methods
+      .batchSyncContributorXp(entries)
+      .accounts({
+        config: configPda,
+        epoch: epochPda,
+        oracle: oracleKeypair.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts(remainingAccounts)
+      .signers([oracleKeypair])
+      .rpc();
+
+    const aliceSnapshot = await program.account.epochSnapshot.fetch(
+      findSnapshotPda(2, contributor1.publicKey)[0]
+    );
+    assert.equal(aliceSnapshot.baselineXp.toString(), "7000");
+    assert.equal(aliceSnapshot.xp.toString(), "600");
+
+    const bobSnapshot = await program.account.epochSnapshot.fetch(
+      findSnapshotPda(2, contributor2.publicKey)[0]
+    );
+    assert.equal(bobSnapshot.xp.toString(), "1800");
+
+    const epoch = await program.account.rewardEpoch.fetch(epochPda);
+    assert.equal(epoch.totalXp.toString(), "2400");
+    assert.equal(epoch.contributorCount, 2);
+
+    const unregisteredSnapshot = await provider.connection.getAccountInfo(
+      findSnapshotPda(2, unregistered)[0]
+    );
+    assert.isNull(unregisteredSnapshot);
+  });
+
+  it("Applies XP updates only after the oracle threshold co-signs", async () => {
+    const [epochPda] = findEpochPda(2);
+    const secondOracle = Keypair.generate();
+    const totalXp = new BN(7700);
+    const [proposalPda] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("xp_proposal"),
+        new BN(2).toArrayLike(Buffer, "le", 8),
+        contributor1.publicKey.toBuffer(),
+        totalXp.toArrayLike(Buffer, "le", 8),
+      ],
+      program.programId
+    );
+
+    await program.methods
+      .setOracleSet([oracleKeypair.publicKey, secondOracle.publicKey], 2)
+      .accounts({
+        config: configPda,
+        admin: admin.publicKey,
+      })
+      .rpc();
+
+    await program.methods
+      .proposeXpUpdate(contributor1.publicKey, totalXp)
+      .accounts({
+        config: configPda,
+        epoch: epochPda,
+        proposal: proposalPda,
+        proposer: oracleKeypair.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([oracleKeypair])
+      .rpc();
+
+    const approveAccounts = {
+      config: configPda,
+      epoch: epochPda,
+      proposal: proposalPda,
+      contributor: findContributorPda(contributor1.publicKey)[0],
+      snapshot: findSnapshotPda(2, contributor1.publicKey)[0],
+      proposer: oracleKeypair.publicKey,
+      systemProgram: SystemProgram.programId,
+    };
+
+    try {
+      await program.methods
+        .approveXpUpdate(contributor1.publicKey, totalXp)
+        .accounts({ ...approveAccounts, approver: oracleKeypair.publicKey })
+        .signers([oracleKeypair])
+        .rpc();
+
+      assert.fail("Should have rejected a second approval from the proposer");
+    } catch (error: any) {
+      assert.include(error.message, "AlreadyApproved");
+    }
+
+    await program.methods
+      .approveXpUpdate(contributor1.publicKey, totalXp)
+      .accounts({ ...approveAccounts, approver: secondOracle.publicKey })
+      .signers([secondOracle])
+      .rpc();
+
+    const snapshot = await program.account.epochSnapshot.fetch(approveAccounts.snapshot);
+    assert.equal(snapshot.xp.toString(), "700");
+    assert.isNull(await provider.connection.getAccountInfo(proposalPda));
+
+    await program.methods
+      .setOracleSet([], 0)
+      .accounts({
+        config: configPda,
+        admin: admin.publicKey,
+      })
+      .rpc();
+  });
+
+  it("Applies oracle-signed XP attestations submitted by the contributor", async () => {
+    const [epochPda] = findEpochPda(2);
+    const totalXp = new BN(4900);
+    const nonce = new BN(1);
+    const message = Buffer.concat([
+      Buffer.from("leaderboard-rewards:xp-attestation"),
+      program.programId.toBuffer(),
+      contributor2.publicKey.toBuffer(),
+      new BN(2).toArrayLike(Buffer, "le", 8),
+      totalXp.toArrayLike(Buffer, "le", 8),
+      nonce.toArrayLike(Buffer, "le", 8),
+    ]);
+    const verifyIx = Ed25519Program.createInstructionWithPrivateKey({
+      privateKey: oracleKeypair.secretKey,
+      message,
+    });
+    const accounts = {
+      config: configPda,
+      epoch: epochPda,
+      contributor: findContributorPda(contributor2.publicKey)[0],
+      snapshot: findSnapshotPda(2, contributor2.publicKey)[0],
+      payer: contributor2.publicKey,
+      instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+      systemProgram: SystemProgram.programId,
+    };
+
+    await program.methods
+      .submitXpAttestation(contributor2.publicKey, totalXp, nonce)
+      .accounts(accounts)
+      .preInstructions([verifyIx])
+      .signers([contributor2])
+      .rpc();
+
+    const snapshot = await program.account.epochSnapshot.fetch(accounts.snapshot);
+    assert.equal(snapshot.xp.toString(), "1900");
+    const contributor = await program.account.contributor.fetch(accounts.contributor);
+    assert.equal(contributor.lastAttestationNonce.toString(), "1");
+
+    try {
+      await program.methods
+        .submitXpAttestation(contributor2.publicKey, totalXp, nonce)
+        .accounts(accounts)
+        .preInstructions([verifyIx])
+        .signers([contributor2])
+        .rpc();
+
+      assert.fail("Should have rejected a replayed attestation");
+    } catch (error: any) {
+      assert.include(error.message, "StaleAttestationNonce");
+    }
+  });
+
+  it("Rejects attestations that would lower a contributor's XP", async () => {
+    const [epochPda] = findEpochPda(2);
+    const [contributor2Pda] = findContributorPda(contributor2.publicKey);
+    const contributorBefore = await program.account.contributor.fetch(contributor2Pda);
+    // An attestation the contributor held on to from before their last oracle sync
+    const staleXp = contributorBefore.totalXp.subn(100);
+    const nonce = contributorBefore.lastAttestationNonce.addn(1);
+    const message = Buffer.concat([
+      Buffer.from("leaderboard-rewards:xp-attestation"),
+      program.programId.toBuffer(),
+      contributor2.publicKey.toBuffer(),
+      new BN(2).toArrayLike(Buffer, "le", 8),
+      staleXp.toArrayLike(Buffer, "le", 8),
+      nonce.toArrayLike(Buffer, "le", 8),
+    ]);
+
+    try {
+      await program.methods
+        .submitXpAttestation(contributor2.publicKey, staleXp, nonce)
+        .accounts({
+          config: configPda,
+          epoch: epochPda,
+          contributor: contributor2Pda,
+          snapshot: findSnapshotPda(2, contributor2.publicKey)[0],
+          payer: contributor2.publicKey,
+          instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+          systemProgram: SystemProgram.programId,
+        })
+        .preInstructions([
+          Ed25519Program.createInstructionWithPrivateKey({
+            privateKey: oracleKeypair.secretKey,
+            message,
+          }),
+        ])
+        .signers([contributor2])
+        .rpc();
+
+      assert.fail("Should have rejected an attestation below the current XP");
+    } catch (error: any) {
+      assert.include(error.message, "AttestationBelowCurrentXp");
+    }
+
+    const contributor = await program.account.contributor.fetch(contributor2Pda);
+    assert.equal(contributor.totalXp.toString(), contributorBefore.totalXp.toString());
+    assert.equal(
+      contributor.totalSbtClaimable.toString(),
+      contributorBefore.totalSbtClaimable.toString()
+    );
+  });
+
+  it("Caps XP issued within an epoch", async () => {
+    const [epochPda] = findEpochPda(2);
+    const params = {
+      maxXpIncrease: new BN(2_000_000),
+      claimWindowSecs: new BN(30 * 24 * 60 * 60),
+      retroactiveWindowSecs: new BN(90 * 24 * 60 * 60),
+      xpToSbtRatio: new BN(100),
+      maxXpPerEpoch: new BN(0),
+      maxSbtPerEpoch: new BN(0),
+      minSyncIntervalSecs: new BN(0),
+    };
+    const epochBefore = await program.account.rewardEpoch.fetch(epochPda);
+
+    await program.methods
+      .updateParams({ ...params, maxXpPerEpoch: epochBefore.xpIssued })
+      .accounts({
+        config: configPda,
+        admin: admin.publicKey,
+      })
+      .rpc();
+
+    try {
+      await program.methods
+        .syncContributorXp(contributor1.publicKey, "alice", new BN(7800), [], [], [])
+        .accounts({
+          config: configPda,
+          epoch: epochPda,
+          contributor: findContributorPda(contributor1.publicKey)[0],
+          snapshot: findSnapshotPda(2, contributor1.publicKey)[0],
+          oracle: oracleKeypair.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([oracleKeypair])
+        .rpc();
+
+      assert.fail("Should have rejected XP beyond the epoch cap");
+    } catch (error: any) {
+      assert.include(error.message, "EpochXpCapExceeded");
+    }
+
+    await program.methods
+      .updateParams(params)
+      .accounts({
+        config: configPda,
+        admin: admin.publicKey,
+      })
+      .rpc();
+  });
+
+  it("Corrects contributor XP downward and claws back unclaimed SBT", async () => {
+    const [epochPda] = findEpochPda(2);
+    const [contributor2Pda] = findContributorPda(contributor2.publicKey);
+    const [snapshotPda] = findSnapshotPda(2, contributor2.publicKey);
+    const contributorBefore = await program.account.contributor.fetch(contributor2Pda);
+    const epochBefore = await program.account.rewardEpoch.fetch(epochPda);
+    const snapshotBefore = await program.account.epochSnapshot.fetch(snapshotPda);
+
+    await program.methods
+      .correctContributorXp(contributor2.publicKey, new BN(4700), 1)
+      .accounts({
+        config: configPda,
+        epoch: epochPda,
+        contributor: contributor2Pda,
+        snapshot: snapshotPda,
+        authority: oracleKeypair.publicKey,
+      })
+      .signers([oracleKeypair])
+      .rpc();
+
+    const contributor = await program.account.contributor.fetch(contributor2Pda);
+    assert.equal(contributor.totalXp.toString(), "4700");
+    assert.equal(
+      contributorBefore.totalSbtClaimable.sub(contributor.totalSbtClaimable).toString(),
+      "20000"
+    );
+
+    const snapshot = await program.account.epochSnapshot.fetch(snapshotPda);
+    assert.equal(snapshot.xp.toString(), snapshotBefore.xp.subn(200).toString());
+
+    const epoch = await program.account.rewardEpoch.fetch(epochPda);
+    assert.equal(epoch.totalXp.toString(), epochBefore.totalXp.subn(200).toString());
+  });
+
+  it("Claims SBT into the wallet's Token-2022 associated account", async () => {
+    const [contributor2Pda] = findContributorPda(contributor2.publicKey);
+    contributor2SbtAccount = getAssociatedTokenAddressSync(
+      sbtMintAddress,
+      contributor2.publicKey,
+      false,
+      TOKEN_2022_PROGRAM_ID
+    );
+    const contributorBefore = await program.account.contributor.fetch(contributor2Pda);
+
+    await program.methods
+      .claimSbtTokens()
+      .accounts({
+        config: configPda,
+        contributor: contributor2Pda,
+        sbtMint: sbtMintAddress,
+        contributorSbtAccount: contributor2SbtAccount,
+        wallet: contributor2.publicKey,
+        token2022Program: TOKEN_2022_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([contributor2])
+      .rpc();
+
+    const contributor = await program.account.contributor.fetch(contributor2Pda);
+    assert.equal(
+      contributor.totalSbtClaimed.toString(),
+      contributorBefore.totalSbtClaimable.toString()
+    );
+
+    const sbtAccount = await getAccount(
+      provider.connection,
+      contributor2SbtAccount,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    assert.equal(
+      sbtAccount.amount.toString(),
+      contributorBefore.totalSbtClaimable.sub(contributorBefore.totalSbtClaimed).toString()
+    );
+  });
+
+  it("Rejects batch claims that include an unfinalized epoch", async () => {
+    const [contributor2Pda] = findContributorPda(contributor2.publicKey);
+    const contributor2UsdcAccount = await createAssociatedTokenAccount(
+      provider.connection,
+      contributor2,
+      usdcMintAddress,
+      contributor2.publicKey
+    );
+
+    try {
+      await program.methods
+        .batchClaimRewards([new BN(2)])
+        .accounts({
+          config: configPda,
+          contributor: contributor2Pda,
+          usdcVault: usdcVault,
+          usdcMint: usdcMintAddress,
+          payoutOwner: contributor2.publicKey,
+          contributorTokenAccount: contributor2UsdcAccount,
+          wallet: contributor2.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts([
+          { pubkey: findEpochPda(2)[0], isSigner: false, isWritable: false },
+          { pubkey: findSnapshotPda(2, contributor2.publicKey)[0], isSigner: false, isWritable: true },
+        ])
+        .signers([contributor2])
+        .rpc();
+
+      assert.fail("Should have thrown error for unfinalized epoch");
+    } catch (error: any) {
+      assert.include(error.message, "EpochNotFinalized");
+    }
+  });
+
+  it("Sets the treasury and refuses to sweep before the claim window expires", async () => {
+    await program.methods
+      .setTreasury(admin.publicKey)
+      .accounts({
+        config: configPda,
+        admin: admin.publicKey,
+      })
+      .rpc();
+
+    const config = await program.account.config.fetch(configPda);
+    assert.equal(config.treasury.toString(), admin.publicKey.toString());
+
+    try {
+      await program.methods
+        .sweepExpiredEpoch(new BN(1), { rollover: {} })
+        .accounts({
+          config: configPda,
+          epoch: findEpochPda(1)[0],
+          nextEpoch: findEpochPda(2)[0],
+          usdcVault: usdcVault,
+          usdcMint: usdcMintAddress,
+          treasuryTokenAccount: null,
+          authority: oracleKeypair.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([oracleKeypair])
+        .rpc();
+
+      assert.fail("Should not sweep an epoch inside its claim window");
+    } catch (error: any) {
+      assert.include(error.message, "ClaimWindowNotExpired");
+    }
+  });
+
+  it("Rejects epochs the vault cannot cover", async () => {
+    const [epochPda] = findEpochPda(3);
+
+    await waitForLastEpochEnd();
+
+    try {
+      await program.methods
+        .createEpoch(new BN(10000000000), null)
+        .accounts({
+          config: configPda,
+          epoch: epochPda,
+          previousEpoch: findEpochPda(2)[0],
+          usdcVault: usdcVault,
+          oracle: oracleKeypair.publicKey,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([oracleKeypair])
+        .rpc();
+
+      assert.fail("Should have rejected an under-funded epoch");
+    } catch (error: any) {
+      assert.include(error.message, "InsufficientVaultBalance");
+    }
+  });
+
+  it("Keeps snapshots open while retroactive claims are possible", async () => {
+    const [snapshot1Pda] = findSnapshotPda(1, contributor1.publicKey);
+
+    try {
+      await program.methods
+        .closeSnapshot(new BN(1), contributor1.publicKey)
+        .accounts({
+          config: configPda,
+          epoch: findEpochPda(1)[0],
+          snapshot: snapshot1Pda,
+          funder: oracleKeypair.publicKey,
+          authority: contributor1.publicKey,
+        })
+        .signers([contributor1])
+        .rpc();
+
+      assert.fail("Should not close a snapshot inside the claim window");
+    } catch (error: any) {
+      assert.include(error.message, "ClaimWindowNotExpired");
+    }
+
+    const snapshotAccount = await provider.connection.getAccountInfo(snapshot1Pda);
+    assert.isNotNull(snapshotAccount);
+  });
+
+  it("Refuses to close an epoch inside its claim window", async () => {
+    try {
+      await program.methods
+        .closeEpoch(new BN(1))
+        .accounts({
+          config: configPda,
+          epoch: findEpochPda(1)[0],
+          oracle: oracleKeypair.publicKey,
+        })
+        .signers([oracleKeypair])
+        .rpc();
+
+      assert.fail("Should not close an unswept epoch");
+    } catch (error: any) {
+      assert.include(error.message, "ClaimWindowNotExpired");
+    }
+  });
+
+  it("Creates a third epoch and finalizes it with a Merkle distribution", async () => {
+    const [epochPda] = findEpochPda(3);
+    const [contributor2Pda] = findContributorPda(contributor2.publicKey);
+
+    await program.methods
+      .createEpoch(new BN(1000000000), null)
+      .accounts({
+        config: configPda,
+        epoch: epochPda,
+        previousEpoch: findEpochPda(2)[0],
+        usdcVault: usdcVault,
+        oracle: oracleKeypair.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([oracleKeypair])
+      .rpc();
+
+    await program.methods
+      .syncContributorXp(contributor2.publicKey, "bob", new BN(5000), [], [], [])
+      .accounts({
+        config: configPda,
+        epoch: epochPda,
+        contributor: contributor2Pda,
+        snapshot: findSnapshotPda(3, contributor2.publicKey)[0],
+        oracle: oracleKeypair.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([oracleKeypair])
+      .rpc();
+
+    await waitForLastEpochEnd();
+
+    const leaves = merkleEpochLeaves();
+    await program.methods
+      .finalizeEpoch(new BN(3), { root: Array.from(merkleRoot(leaves)), leafCount: leaves.length })
+      .accounts({
+        config: configPda,
+        epoch: epochPda,
+        oracle: oracleKeypair.publicKey,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([oracleKeypair])
+      .rpc();
+
+    const epoch = await program.account.rewardEpoch.fetch(epochPda);
+    assert.equal(epoch.finalized, true);
+    assert.equal(epoch.merkleLeafCount, leaves.length);
+  });
+
+  it("Rejects snapshot claims on Merkle epochs", async () => {
+    try {
+      await program.methods
+        .claimUsdcRewards(new BN(3))
+        .accounts({
+          config: configPda,
+          epoch: findEpochPda(3)[0],
+          snapshot: findSnapshotPda(3, contributor2.publicKey)[0],
+          contributor: findContributorPda(contributor2.publicKey)[0],
+          usdcVault: usdcVault,
+          usdcMint: usdcMintAddress,
+          payoutOwner: contributor2.publicKey,
+          contributorTokenAccount: getAssociatedTokenAddressSync(usdcMintAddress, contributor2.publicKey),
+          wallet: contributor2.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([contributor2])
+        .rpc();
+
+      assert.fail("Should have required a Merkle proof");
+    } catch (error: any) {
+      assert.include(error.message, "MerkleDistributionActive");
+    }
+  });
+
+  it("Rejects retroactive claims on Merkle epochs", async () => {
+    try {
+      await program.methods
+        .claimRetroactiveUsdc(new BN(3), new BN(2000), [])
+        .accounts({
+          config: configPda,
+          epoch: findEpochPda(3)[0],
+          snapshot: findSnapshotPda(3, contributor3.publicKey)[0],
+          contributor: findContributorPda(contributor3.publicKey)[0],
+          usdcVault: usdcVault,
+          usdcMint: usdcMintAddress,
+          payoutOwner: contributor3.publicKey,
+          contributorTokenAccount: getAssociatedTokenAddressSync(usdcMintAddress, contributor3.publicKey),
+          wallet: contributor3.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([contributor3])
+        .rpc();
+
+      assert.fail("Should not pay retroactive shares out of a Merkle distribution");
+    } catch (error: any) {
+      assert.include(error.message, "MerkleDistributionActive");
+    }
+  });
+
+  it("Claims USDC with a Merkle proof exactly once", async () => {
+    const [epochPda] = findEpochPda(3);
+    const contributor2UsdcAccount = getAssociatedTokenAddressSync(usdcMintAddress, contributor2.publicKey);
+    const leaves = merkleEpochLeaves();
+    const proof = merkleProof(leaves, 0).map((node) => Array.from(node));
+    const claim = () =>
+      program.methods
+        .claimUsdcWithProof(new BN(3), 0, MERKLE_XP, MERKLE_AMOUNT, proof)
+        .accounts({
+          config: configPda,
+          epoch: epochPda,
+          contributor: findContributorPda(contributor2.publicKey)[0],
+          usdcVault: usdcVault,
+          usdcMint: usdcMintAddress,
+          payoutOwner: contributor2.publicKey,
+          contributorTokenAccount: contributor2UsdcAccount,
+          wallet: contributor2.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([contributor2])
+        .rpc();
+
+    const balanceBefore = await provider.connection.getTokenAccountBalance(contributor2UsdcAccount);
+    await claim();
+    const balanceAfter = await provider.connection.getTokenAccountBalance(contributor2UsdcAccount);
+    assert.equal(
+      new BN(balanceAfter.value.amount).sub(new BN(balanceBefore.value.amount)).toString(),
+      MERKLE_AMOUNT.toString()
+    );
+
+    const epoch = await program.account.rewardEpoch.fetch(epochPda);
+    assert.equal(epoch.claimBitmap[0] & 1, 1);
+    assert.equal(epoch.usdcClaimedTotal.toString(), MERKLE_AMOUNT.toString());
+
+    try {
+      await claim();
+      assert.fail("Should have rejected a second claim of the same leaf");
+    } catch (error: any) {
+      assert.include(error.message, "AlreadyClaimed");
+    }
+  });
+
+  it("Batch claims several epochs in a single transfer", async () => {
+    const [contributor2Pda] = findContributorPda(contributor2.publicKey);
+    const contributor2UsdcAccount = getAssociatedTokenAddressSync(usdcMintAddress, contributor2.publicKey);
+    const epochNumbers = [1, 2];
+
+    let expectedReward = new BN(0);
+    for (const epochNumber of epochNumbers) {
+      const epoch = await program.account.rewardEpoch.fetch(findEpochPda(epochNumber)[0]);
+      const snapshot = await program.account.epochSnapshot.fetch(
+        findSnapshotPda(epochNumber, contributor2.publicKey)[0]
+      );
+      assert.equal(snapshot.usdcClaimed, false);
+      expectedReward = expectedReward.add(
+        snapshot.xp.mul(epoch.usdcRewardAmount).div(epoch.totalXp.add(epoch.unregisteredTotalXp))
+      );
+    }
+    const balanceBefore = await provider.connection.getTokenAccountBalance(contributor2UsdcAccount);
+
+    const signature = await program.methods
+      .batchClaimRewards(epochNumbers.map((epochNumber) => new BN(epochNumber)))
+      .accounts({
+        config: configPda,
+        contributor: contributor2Pda,
+        usdcVault: usdcVault,
+        usdcMint: usdcMintAddress,
+        payoutOwner: contributor2.publicKey,
+        contributorTokenAccount: contributor2UsdcAccount,
+        wallet: contributor2.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts(
+        epochNumbers.flatMap((epochNumber) => [
+          { pubkey: findEpochPda(epochNumber)[0], isSigner: false, isWritable: true },
+          { pubkey: findSnapshotPda(epochNumber, contributor2.publicKey)[0], isSigner: false, isWritable: true },
+        ])
+      )
+      .signers([contributor2])
+      .rpc({ commitment: "confirmed" });
+
+    const balanceAfter = await provider.connection.getTokenAccountBalance(contributor2UsdcAccount);
+    assert.equal(
+      new BN(balanceAfter.value.amount).sub(new BN(balanceBefore.value.amount)).toString(),
+      expectedReward.toString()
+    );
+
+    for (const epochNumber of epochNumbers) {
+      const snapshot = await program.account.epochSnapshot.fetch(
+        findSnapshotPda(epochNumber, contributor2.publicKey)[0]
+      );
+      assert.equal(snapshot.usdcClaimed, true);
+    }
+
+    const tx = await provider.connection.getParsedTransaction(signature, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const transfers = tx.meta.innerInstructions
+      .flatMap((inner) => inner.instructions)
+      .filter((ix: any) => ix.parsed?.type === "transferChecked");
+    assert.equal(transfers.length, 1);
+  });
+
+  it("Keeps paying the current address while a payout change is pending", async () => {
+    const [epochPda] = findEpochPda(2);
+    const [contributor1Pda] = findContributorPda(contributor1.publicKey);
+    const [snapshotPda] = findSnapshotPda(2, contributor1.publicKey);
+    const contributor = await program.account.contributor.fetch(contributor1Pda);
+    assert.equal(contributor.pendingPayoutAddress.toString(), coldWallet.publicKey.toString());
+
+    const epochData = await program.account.rewardEpoch.fetch(epochPda);
+    const snapshot = await program.account.epochSnapshot.fetch(snapshotPda);
+    const expectedReward = snapshot.xp
+      .mul(epochData.usdcRewardAmount)
+      .div(epochData.totalXp.add(epochData.unregisteredTotalXp));
+    const balanceBefore = await provider.connection.getTokenAccountBalance(contributor1UsdcAccount);
+
+    await program.methods
+      .claimUsdcRewards(new BN(2))
+      .accounts({
+        config: configPda,
+        epoch: epochPda,
+        snapshot: snapshotPda,
+        contributor: contributor1Pda,
+        usdcVault: usdcVault,
+        usdcMint: usdcMintAddress,
+        payoutOwner: contributor1.publicKey,
+        contributorTokenAccount: contributor1UsdcAccount,
+        wallet: contributor1.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([contributor1])
+      .rpc();
+
+    const balanceAfter = await provider.connection.getTokenAccountBalance(contributor1UsdcAccount);
+    assert.equal(
+      new BN(balanceAfter.value.amount).sub(new BN(balanceBefore.value.amount)).toString(),
+      expectedReward.toString()
+    );
+  });
+
+  it("Lets a contributor close their claimed snapshot and refunds its funder", async () => {
+    const [epochPda] = findEpochPda(2);
+    const [snapshotPda] = findSnapshotPda(2, contributor1.publicKey);
+    const snapshot = await program.account.epochSnapshot.fetch(snapshotPda);
+    assert.equal(snapshot.usdcClaimed, true);
+
+    const epochBefore = await program.account.rewardEpoch.fetch(epochPda);
+    assert.equal(epochBefore.unregisteredTotalXp.toString(), "0");
+    const snapshotLamports = (await provider.connection.getAccountInfo(snapshotPda)).lamports;
+    const funderBefore = await provider.connection.getBalance(snapshot.funder);
+
+    await program.methods
+      .closeSnapshot(new BN(2), contributor1.publicKey)
+      .accounts({
+        config: configPda,
+        epoch: epochPda,
+        snapshot: snapshotPda,
+        funder: snapshot.funder,
+        authority: contributor1.publicKey,
+      })
+      .signers([contributor1])
+      .rpc();
+
+    assert.isNull(await provider.connection.getAccountInfo(snapshotPda));
+    assert.equal(
+      await provider.connection.getBalance(snapshot.funder),
+      funderBefore + snapshotLamports
+    );
+
+    const epoch = await program.account.rewardEpoch.fetch(epochPda);
+    assert.equal(epoch.snapshotCount, epochBefore.snapshotCount - 1);
   });
 });